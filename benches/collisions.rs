@@ -0,0 +1,85 @@
+//! Compare the brute force collision loops with the spatial grid.
+//!
+//! `cargo bench --bench collisions` runs it with a few crowd sizes.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use quicksilver::geom::Vector;
+use rand::prelude::*;
+use rand_xorshift::XorShiftRng;
+use chaos::{
+    ARENA,
+    enemy::{Enemy, EnemyKind},
+    grid::Grids,
+    shot::Shot,
+};
+
+const ROUNDS: u32 = 50;
+
+fn crowd(rng: &mut XorShiftRng, enemies: usize, shots: usize) -> (Vec<Enemy>, Vec<Shot>) {
+    let mut pos = || Vector::new(rng.gen_range(0.0, ARENA.x), rng.gen_range(0.0, ARENA.y));
+    let e = (0..enemies)
+        .map(|i| Enemy::new(EnemyKind::Slime, pos(), 1 + i as u32 % 4))
+        .collect();
+    let s = (0..shots)
+        .map(|_| Shot::new(pos(), Vector::X, 1, 1))
+        .collect();
+    (e, s)
+}
+
+fn overlap(a: Vector, ra: f32, b: Vector, rb: f32) -> bool {
+    (a - b).len2() < (ra + rb).powi(2)
+}
+
+/// What the enemies did before: each one looks at every shot
+fn brute(enemies: &[Enemy], shots: &[Shot]) -> usize {
+    let mut hits = 0;
+    for e in enemies {
+        for s in shots {
+            if overlap(e.pos, e.radius, s.pos, s.radius) {
+                hits += 1;
+            }
+        }
+    }
+    hits
+}
+
+/// What they do now, including the rebuild of the grid each tick
+fn grid(grids: &mut Grids, enemies: &[Enemy], shots: &[Shot]) -> usize {
+    grids.shots.rebuild(shots.iter().map(|s| (s.pos, s.radius)));
+    let mut hits = 0;
+    for e in enemies {
+        for i in grids.shots.query(e.pos, e.radius) {
+            if overlap(e.pos, e.radius, shots[i].pos, shots[i].radius) {
+                hits += 1;
+            }
+        }
+    }
+    hits
+}
+
+fn time(mut f: impl FnMut() -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut hits = 0;
+    for _ in 0..ROUNDS {
+        hits = black_box(f());
+    }
+    (start.elapsed() / ROUNDS, hits)
+}
+
+fn main() {
+    let mut rng = XorShiftRng::seed_from_u64(42);
+    let mut grids = Grids::default();
+
+    println!("{:>8} {:>8} {:>12} {:>12}", "enemies", "shots", "brute", "grid");
+    for &(n_enemies, n_shots) in &[(20, 10), (100, 50), (400, 200), (1000, 600), (3000, 2000)] {
+        let (enemies, shots) = crowd(&mut rng, n_enemies, n_shots);
+
+        let (brute_time, brute_hits) = time(|| brute(&enemies, &shots));
+        let (grid_time, grid_hits) = time(|| grid(&mut grids, &enemies, &shots));
+        assert_eq!(brute_hits, grid_hits, "The grid must find the same collisions");
+
+        println!("{:>8} {:>8} {:>12?} {:>12?}", n_enemies, n_shots, brute_time, grid_time);
+    }
+}
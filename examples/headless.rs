@@ -0,0 +1,55 @@
+//! Run the game without any window, with a mouse that circles
+//! around the center of the screen and fires twice a second.
+//!
+//! `cargo run --example headless -- 10000` runs for 10000 ticks.
+//! The run is recorded, and its replay is checked to give the same score.
+//! The sounds are mixed too, into a backend that plays nothing.
+
+use quicksilver::geom::Vector;
+use chaos::{Game, ARENA, Action, Recorder, Replay};
+use chaos::audio::{Mixer, NullBackend};
+use chaos::sfx::SAMPLE_RATE;
+use chaos::music::MusicState;
+
+fn main() {
+    let ticks: u32 = std::env::args()
+        .nth(1)
+        .and_then(|a| a.parse().ok())
+        .unwrap_or(3000);
+
+    let mut game = Game::new();
+    let mut recorder = Recorder::new(game.seed, game.rules.clone());
+    let mut mixer = Mixer::new(Box::new(NullBackend::default()));
+    let mut events = 0;
+    for t in 0..ticks {
+        let angle = t as f32 * 2.0;
+        let mouse = ARENA / 2.0 + Vector::from_angle(angle) * 250.0;
+
+        if t % 15 == 0 {
+            let aim = game.enemies.first().map_or(mouse, |e| e.pos);
+            recorder.action(Action::Fire, aim);
+            game.action(Action::Fire, aim);
+        }
+        recorder.tick(mouse.into());
+        // The events of the actions are cleared by the update
+        let mut tick_events = game.take_events();
+        game.update(mouse.into());
+        tick_events.extend(game.take_events());
+
+        events += tick_events.len();
+        mixer.handle(&tick_events);
+        mixer.music.state = MusicState::of(&game);
+        mixer.mix(SAMPLE_RATE as usize / 30);
+
+        if game.player.life == 0 {
+            println!("Died at tick {}", t);
+            break;
+        }
+    }
+
+    println!("Wave {} - Score {}", game.wave, game.score);
+    println!("{} game events, {} sounds still playing", events, mixer.voices());
+
+    let replay = Replay::from_json(&recorder.replay(game.score).to_json()).unwrap();
+    println!("Replay verified: {}", replay.verify());
+}
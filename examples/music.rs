@@ -0,0 +1,33 @@
+//! Render the music of a made up run to a WAV file, without any audio device.
+//!
+//! `cargo run --example music -- music.wav` goes from a calm start to full
+//! chaos, through a break between waves, and ends with a game over.
+
+use chaos::music::{Music, MusicState};
+use chaos::sfx::{self, SAMPLE_RATE};
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| "music.wav".to_string());
+
+    // (seconds, state)
+    let script = [
+        (8, MusicState { chaos: 0.1, calm: false, game_over: false }),
+        (8, MusicState { chaos: 0.4, calm: false, game_over: false }),
+        (6, MusicState { chaos: 0.4, calm: true, game_over: false }),
+        (8, MusicState { chaos: 0.7, calm: false, game_over: false }),
+        (8, MusicState { chaos: 1.0, calm: false, game_over: false }),
+        (4, MusicState { chaos: 1.0, calm: false, game_over: true }),
+    ];
+
+    let mut music = Music::new(0);
+    let mut samples = vec![];
+    for (seconds, state) in &script {
+        music.state = *state;
+        samples.extend(music.render(seconds * SAMPLE_RATE as usize));
+        println!("{:?} at {} bpm", state, music.tempo());
+    }
+
+    let peak = samples.iter().fold(0.0f32, |p, s| p.max(s.abs()));
+    std::fs::write(&path, sfx::wav(&samples)).expect("Could not write the music");
+    println!("{} - {} samples, peak {:.2}", path, samples.len(), peak);
+}
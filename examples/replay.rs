@@ -0,0 +1,18 @@
+//! Check the score claimed by a replay file, without any window.
+//!
+//! `cargo run --example replay -- run.json`
+
+use chaos::Replay;
+
+fn main() {
+    let path = std::env::args().nth(1).expect("usage: replay <file>");
+    let data = std::fs::read(&path).expect("Could not read the replay");
+    let replay = Replay::from_json(&data).unwrap_or_else(|e| panic!("{}: {}", path, e));
+
+    let game = replay.play();
+    println!("Seed {} - {} ticks", replay.seed, replay.ticks.len());
+    println!("Wave {} - Score {} (claimed {})", game.wave, game.score, replay.score);
+    if game.score != replay.score {
+        std::process::exit(1);
+    }
+}
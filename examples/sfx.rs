@@ -0,0 +1,38 @@
+//! Write every sound effect of the game as a WAV file, without any audio device.
+//!
+//! `cargo run --example sfx -- sounds/` writes `sounds/Shot.wav` and so on.
+//! `cargo run --example sfx -- sounds/ params.json` only renders the
+//! `SfxParams` of the json file, to tune a new sound.
+
+use std::path::Path;
+
+use chaos::sfx::{self, Sfx, SfxParams};
+
+fn write(dir: &Path, name: &str, params: &SfxParams) {
+    let samples = params.synth(0);
+    let path = dir.join(format!("{}.wav", name));
+    std::fs::write(&path, sfx::wav(&samples)).expect("Could not write the sound");
+    println!("{} - {} samples", path.display(), samples.len());
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let dir = args.next().unwrap_or_else(|| ".".to_string());
+    let dir = Path::new(&dir);
+    std::fs::create_dir_all(dir).expect("Could not create the directory");
+
+    match args.next() {
+        Some(file) => {
+            let data = std::fs::read(&file).expect("Could not read the parameters");
+            let params: SfxParams = serde_json::from_slice(&data)
+                .unwrap_or_else(|e| panic!("{}: {}", file, e));
+            let name = Path::new(&file).file_stem().unwrap().to_string_lossy();
+            write(dir, &name, &params);
+        }
+        None => {
+            for sound in &Sfx::ALL {
+                write(dir, &format!("{:?}", sound), &sound.params());
+            }
+        }
+    }
+}
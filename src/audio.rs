@@ -0,0 +1,298 @@
+//! Turns the game events into sound.
+//!
+//! The `Mixer` plays the synthesized effects of `sfx` on a few voices, over
+//! the generated `music`, and sends the mixed samples to an `AudioBackend`.
+//! It has its own random generator, so sounds never change the course of
+//! a run.
+
+use std::collections::HashMap;
+
+use rand::prelude::*;
+use rand_xorshift::XorShiftRng;
+
+use super::GameEvent;
+use crate::sfx::Sfx;
+use crate::music::Music;
+
+/// Sounds playing at the same time, the oldest one is cut past this
+const MAX_VOICES: usize = 16;
+/// Voices playing the same sound, so a hundred hits do not drown the rest
+const MAX_SAME_SOUND: usize = 3;
+/// Each sound is played up to this much higher or lower
+const PITCH_VARIATION: f32 = 0.08;
+
+/// Sounds are grouped so each group can be made louder or quieter.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+    Player,
+    Enemies,
+    Pickups,
+    Music,
+}
+
+impl Category {
+    pub fn of(sound: Sfx) -> Self {
+        match sound {
+            Sfx::Shot | Sfx::Laser | Sfx::PlayerHit => Category::Player,
+            Sfx::EnemyHit | Sfx::Split | Sfx::EnemyDeath => Category::Enemies,
+            Sfx::PowerUp => Category::Pickups,
+        }
+    }
+}
+
+/// Where the mixed samples go.
+pub trait AudioBackend {
+    fn write(&mut self, samples: &[f32]);
+}
+
+/// Plays nothing, for tests and machines without sound.
+#[derive(Default)]
+pub struct NullBackend {
+    /// Samples received so far
+    pub written: usize,
+    /// Loudest of them
+    pub peak: f32,
+}
+
+impl AudioBackend for NullBackend {
+    fn write(&mut self, samples: &[f32]) {
+        self.written += samples.len();
+        self.peak = samples.iter().fold(self.peak, |p, s| p.max(s.abs()));
+    }
+}
+
+/// The sound for an event, and its pitch before the random variation
+fn sound_of(event: &GameEvent) -> (Sfx, f32) {
+    match event {
+        GameEvent::ShotFired { laser: false } => (Sfx::Shot, 1.0),
+        GameEvent::ShotFired { laser: true } => (Sfx::Laser, 1.0),
+        GameEvent::EnemyHit => (Sfx::EnemyHit, 1.0),
+        GameEvent::EnemySplit => (Sfx::Split, 1.0),
+        GameEvent::EnemyKilled(_) => (Sfx::EnemyDeath, 1.0),
+        GameEvent::EnemyDetonated(_) => (Sfx::EnemyDeath, 0.7),
+        GameEvent::BossKilled => (Sfx::EnemyDeath, 0.5),
+        GameEvent::PlayerHurt => (Sfx::PlayerHit, 1.0),
+        GameEvent::PowerupTaken(_) => (Sfx::PowerUp, 1.0),
+        GameEvent::Rerolled => (Sfx::PowerUp, 1.5),
+        GameEvent::Dashed => (Sfx::Shot, 0.6),
+        GameEvent::Zapped => (Sfx::Laser, 1.6),
+        GameEvent::StatusEnded(_) => (Sfx::PowerUp, 0.5),
+        GameEvent::Evolved(_) => (Sfx::PowerUp, 0.8),
+        GameEvent::WaveStarted(_) => (Sfx::PowerUp, 0.7),
+    }
+}
+
+struct Voice {
+    sound: Sfx,
+    /// In samples of the sound, it moves by `pitch` each output sample
+    pos: f32,
+    pitch: f32,
+    /// When it started, to know which voice to cut
+    started: u64,
+}
+
+pub struct Mixer {
+    sounds: HashMap<Sfx, Vec<f32>>,
+    voices: Vec<Voice>,
+    volumes: HashMap<Category, f32>,
+    pub master: f32,
+    /// Set its state each tick so it follows the game
+    pub music: Music,
+    rng: XorShiftRng,
+    backend: Box<dyn AudioBackend>,
+    /// Sounds started so far
+    count: u64,
+    buffer: Vec<f32>,
+}
+
+impl Mixer {
+    /// Synthesizes every sound, which takes a little while.
+    pub fn new(backend: Box<dyn AudioBackend>) -> Self {
+        Mixer {
+            sounds: Sfx::ALL.iter().map(|&s| (s, s.params().synth(s as u64))).collect(),
+            voices: vec![],
+            volumes: HashMap::new(),
+            master: 1.0,
+            music: Music::default(),
+            rng: XorShiftRng::seed_from_u64(0),
+            backend,
+            count: 0,
+            buffer: vec![],
+        }
+    }
+
+    pub fn volume(&self, category: Category) -> f32 {
+        self.volumes.get(&category).copied().unwrap_or(1.0)
+    }
+
+    pub fn set_volume(&mut self, category: Category, volume: f32) {
+        self.volumes.insert(category, volume.max(0.0));
+    }
+
+    /// How many sounds are playing
+    pub fn voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Start the sounds of the events
+    pub fn handle(&mut self, events: &[GameEvent]) {
+        for (sound, pitch) in events.iter().map(sound_of) {
+            self.play(sound, pitch);
+        }
+    }
+
+    /// Start a sound, `pitch` 2.0 being an octave higher.
+    pub fn play(&mut self, sound: Sfx, pitch: f32) {
+        let variation = self.rng.gen_range(-PITCH_VARIATION, PITCH_VARIATION);
+        let voice = Voice {
+            sound,
+            pos: 0.0,
+            pitch: pitch * (1.0 + variation),
+            started: self.count,
+        };
+        self.count += 1;
+
+        let same = self.voices.iter().filter(|v| v.sound == sound).count();
+        let cut = if same >= MAX_SAME_SOUND {
+            self.oldest(|v| v.sound == sound)
+        } else if self.voices.len() >= MAX_VOICES {
+            self.oldest(|_| true)
+        } else {
+            None
+        };
+
+        match cut {
+            Some(i) => self.voices[i] = voice,
+            None => self.voices.push(voice),
+        }
+    }
+
+    fn oldest(&self, filter: impl Fn(&Voice) -> bool) -> Option<usize> {
+        self.voices
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| filter(v))
+            .min_by_key(|(_, v)| v.started)
+            .map(|(i, _)| i)
+    }
+
+    /// Mix the next `len` samples and send them to the backend
+    pub fn mix(&mut self, len: usize) {
+        self.buffer.clear();
+        self.buffer.resize(len, 0.0);
+
+        for voice in &mut self.voices {
+            let samples = &self.sounds[&voice.sound];
+            let category = self.volumes.get(&Category::of(voice.sound)).copied().unwrap_or(1.0);
+            let volume = self.master * category;
+
+            for out in &mut self.buffer {
+                // Linear interpolation between the two closest samples
+                let i = voice.pos as usize;
+                if i + 1 >= samples.len() {
+                    break;
+                }
+                let frac = voice.pos - i as f32;
+                *out += (samples[i] * (1.0 - frac) + samples[i + 1] * frac) * volume;
+                voice.pos += voice.pitch;
+            }
+        }
+        let sounds = &self.sounds;
+        self.voices.retain(|v| (v.pos as usize) + 1 < sounds[&v.sound].len());
+
+        let volume = self.master * self.volume(Category::Music);
+        for (s, m) in self.buffer.iter_mut().zip(self.music.render(len)) {
+            *s = (*s + m * volume).clamp(-1.0, 1.0);
+        }
+        self.backend.write(&self.buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sfx::SAMPLE_RATE;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Keeps what it is sent for the test to listen to
+    #[derive(Clone, Default)]
+    struct Speaker(Rc<RefCell<Vec<f32>>>);
+
+    impl AudioBackend for Speaker {
+        fn write(&mut self, samples: &[f32]) {
+            self.0.borrow_mut().extend_from_slice(samples);
+        }
+    }
+
+    /// A mixer without music, and what it plays
+    fn mixer() -> (Mixer, Speaker) {
+        let speaker = Speaker::default();
+        let mut mixer = Mixer::new(Box::new(speaker.clone()));
+        mixer.set_volume(Category::Music, 0.0);
+        (mixer, speaker)
+    }
+
+    fn peak(speaker: &Speaker) -> f32 {
+        speaker.0.borrow().iter().fold(0.0, |p, s| p.max(s.abs()))
+    }
+
+    #[test]
+    fn voices_are_limited() {
+        let (mut mixer, _) = mixer();
+        for _ in 0..10 {
+            mixer.play(Sfx::Shot, 1.0);
+        }
+        assert_eq!(mixer.voices(), MAX_SAME_SOUND);
+
+        for _ in 0..MAX_SAME_SOUND {
+            for &sound in &Sfx::ALL {
+                mixer.play(sound, 1.0);
+            }
+        }
+        assert_eq!(mixer.voices(), MAX_VOICES);
+    }
+
+    #[test]
+    fn nothing_is_silence() {
+        let (mut mixer, speaker) = mixer();
+        mixer.handle(&[]);
+        mixer.mix(4096);
+        assert_eq!(speaker.0.borrow().len(), 4096);
+        assert_eq!(peak(&speaker), 0.0);
+    }
+
+    #[test]
+    fn category_volumes() {
+        let (mut loud, loud_out) = mixer();
+        let (mut quiet, quiet_out) = mixer();
+        let (mut muted, muted_out) = mixer();
+        quiet.set_volume(Category::Enemies, 0.5);
+        muted.set_volume(Category::Enemies, 0.0);
+        assert_eq!(muted.volume(Category::Enemies), 0.0);
+        assert_eq!(muted.volume(Category::Player), 1.0);
+
+        let events = [GameEvent::EnemyHit];
+        for mixer in [&mut loud, &mut quiet, &mut muted] {
+            mixer.handle(&events);
+            mixer.mix(2048);
+        }
+        assert!(peak(&loud_out) > 0.0);
+        assert!((peak(&quiet_out) - peak(&loud_out) / 2.0).abs() < 1e-6);
+        assert_eq!(peak(&muted_out), 0.0);
+
+        // The other categories still play
+        muted.handle(&[GameEvent::PlayerHurt]);
+        muted.mix(2048);
+        assert!(peak(&muted_out) > 0.0);
+    }
+
+    #[test]
+    fn master_mutes_everything() {
+        let (mut mixer, speaker) = mixer();
+        mixer.set_volume(Category::Music, 1.0);
+        mixer.master = 0.0;
+        mixer.handle(&[GameEvent::PlayerHurt, GameEvent::BossKilled]);
+        mixer.mix(SAMPLE_RATE as usize);
+        assert_eq!(peak(&speaker), 0.0);
+    }
+}
@@ -1,6 +1,5 @@
 use quicksilver::geom::Vector;
-#[allow(unused_imports)]
-use quicksilver::graphics::{Color, Graphics, Vertex, Mesh, Element};
+use quicksilver::graphics::Color;
 use rand_distr::*;
 use super::{XorShiftRng, SIZE};
 
@@ -25,13 +24,12 @@ impl BgPoint {
         self.angle += (score + 2.0).log2();
     }
 
-    fn draw_pos(&self) -> Vector {
+    pub fn draw_pos(&self) -> Vector {
         self.pos + Vector::from_angle(self.angle) * BG_WIGGLE
     }
 }
 
 pub struct Background {
-    pub t: f32,
     pub points: Vec<BgPoint>,
     pub color: Color,
 }
@@ -42,7 +40,6 @@ impl Background {
         let angle = Uniform::new(0.0, 10.0);
         let mut a = 0.0;
         Background {
-            t: 0.0,
             points: (0..230).map(|i| {
                 a += angle.sample(rng);
                 BgPoint {
@@ -66,13 +63,4 @@ impl Background {
             self.points[i].teleport(rng);
         }
     }
-
-    pub fn draw(&self, gfx: &mut Graphics, _score: u32) {
-        let pts : Vec<Vector> = self.points
-            .iter()
-            .map(|p| p.draw_pos())
-            .collect();
-
-        gfx.stroke_path(&pts, self.color);
-    }
-}
\ No newline at end of file
+}
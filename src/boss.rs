@@ -0,0 +1,242 @@
+use quicksilver::{
+    geom::{Rectangle, Vector},
+    graphics::Color,
+};
+use rand_distr::*;
+use rand_xorshift::XorShiftRng;
+use serde::{Serialize, Deserialize};
+
+use super::{Particle, Shape, Game, Enemy, EnemyKind, Shot, Faction, Power, GameEvent};
+
+const CHASE_SPEED: f32 = 3.0;
+const CHARGE_SPEED: f32 = 28.0;
+const BULLET_SPEED: f32 = 9.0;
+
+/// How the boss waves are made, in the wave script.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BossDef {
+    /// Every `every`-th wave is a boss wave
+    pub every: u32,
+    pub life: i32,
+    /// Added to the life of each next boss
+    pub life_step: i32,
+    pub radius: f32,
+    /// Level of the slimes it spawns
+    pub minion_level: u32,
+    /// Bullets in each radial burst
+    pub bullets: u32,
+    /// Offered when the boss dies
+    pub powerups: Vec<Power>,
+    pub picks: usize,
+}
+
+/// What the boss is busy doing, the counter is the frames left.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BossPhase {
+    /// Slowly follows the player
+    Chase(u32),
+    /// Stops and aims, then rushes in a straight line
+    Charge(u32, Vector),
+    /// Calls slimes around itself
+    Spawn(u32),
+    /// Fires rings of bullets
+    Burst(u32),
+}
+
+impl BossPhase {
+    /// The phase that comes after this one
+    fn next(self, target: Vector, pos: Vector) -> Self {
+        match self {
+            BossPhase::Chase(_) => {
+                let dir = target - pos;
+                let dir = if dir.len2() > 0.0 { dir.normalize() } else { Vector::X };
+                BossPhase::Charge(45, dir)
+            }
+            BossPhase::Charge(..) => BossPhase::Spawn(40),
+            BossPhase::Spawn(_) => BossPhase::Burst(60),
+            BossPhase::Burst(_) => BossPhase::Chase(60),
+        }
+    }
+
+    fn frames_left(self) -> u32 {
+        match self {
+            BossPhase::Chase(t)
+            | BossPhase::Charge(t, _)
+            | BossPhase::Spawn(t)
+            | BossPhase::Burst(t) => t,
+        }
+    }
+
+    fn tick(self) -> Self {
+        match self {
+            BossPhase::Chase(t) => BossPhase::Chase(t - 1),
+            BossPhase::Charge(t, dir) => BossPhase::Charge(t - 1, dir),
+            BossPhase::Spawn(t) => BossPhase::Spawn(t - 1),
+            BossPhase::Burst(t) => BossPhase::Burst(t - 1),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Boss {
+    pub pos: Vector,
+    pub radius: f32,
+    pub life: i32,
+    pub max_life: i32,
+    pub phase: BossPhase,
+    /// Frames since it appeared
+    pub t: u32,
+    minion_level: u32,
+    bullets: u32,
+}
+
+impl Boss {
+    /// The boss of the `nb`-th boss wave, starting at 0. It comes
+    /// from the middle of the top of the `view`.
+    pub fn new(def: &BossDef, nb: u32, view: &Rectangle) -> Self {
+        let life = def.life + def.life_step * nb as i32;
+        Boss {
+            pos: Vector::new(view.pos.x + view.size.x / 2.0, view.pos.y - def.radius),
+            radius: def.radius,
+            life,
+            max_life: life,
+            phase: BossPhase::Chase(90),
+            t: 0,
+            minion_level: def.minion_level,
+            bullets: def.bullets,
+        }
+    }
+
+    pub fn alive(&self) -> bool {
+        self.life > 0
+    }
+
+    pub fn color(&self) -> Color {
+        match self.phase {
+            BossPhase::Chase(_) => Color::PURPLE,
+            BossPhase::Charge(..) => Color::RED,
+            BossPhase::Spawn(_) => Color::GREEN,
+            BossPhase::Burst(_) => Color::ORANGE,
+        }
+    }
+
+    pub fn update(&mut self, game: &mut Game) {
+        // In slow motion, it moves slower and its phases last longer
+        let (speed, clock) = (game.time_scale(), game.clock_ticks());
+        if clock {
+            self.t += 1;
+        }
+        let to_player = game.player.pos - self.pos;
+
+        match self.phase {
+            BossPhase::Chase(_) => {
+                if to_player.len() > 1.0 {
+                    self.pos += to_player.normalize() * CHASE_SPEED * speed;
+                }
+            }
+            BossPhase::Charge(t, dir) => {
+                // Half of the time to aim, the other half to rush
+                if t < 20 {
+                    self.pos += dir * CHARGE_SPEED * speed;
+                }
+                if t == 20 && clock {
+                    game.shake += 6;
+                }
+            }
+            BossPhase::Spawn(t) => {
+                if t % 10 == 0 && clock {
+                    let angle = Uniform::new(0.0, 360.0).sample(&mut game.rng);
+                    let pos = self.pos + Vector::from_angle(angle) * self.radius;
+                    let kb = Vector::from_angle(angle) * 30.0;
+                    game.enemies.push(Enemy::new_kb(EnemyKind::Slime, pos, self.minion_level, kb));
+                }
+            }
+            BossPhase::Burst(t) => {
+                if t % 20 == 0 && clock {
+                    // Each ring is turned so the holes are not always at the same place
+                    let offset = (self.t % 360) as f32;
+                    game.shots.extend((0..self.bullets).map(|i| {
+                        let a = offset + 360.0 * i as f32 / self.bullets as f32;
+                        Shot::hostile(
+                            self.pos + Vector::from_angle(a) * self.radius,
+                            Vector::from_angle(a) * BULLET_SPEED,
+                            Color::RED,
+                        )
+                    }));
+                }
+            }
+        }
+
+        // Stay in the arena, once it entered it
+        if self.t > 60 {
+            self.pos = self.pos.clamp(Vector::ZERO, game.arena);
+        }
+
+        if clock {
+            self.phase = if self.phase.frames_left() == 0 {
+                self.phase.next(game.player.pos, self.pos)
+            } else {
+                self.phase.tick()
+            };
+        }
+
+        // Hits
+        for i in game.grids.shots.query(self.pos, self.radius) {
+            let s = &mut game.shots[i];
+            if s.owner == Faction::Player && s.pierce > 0
+                && (s.pos - self.pos).len2() < (s.radius + self.radius).powi(2) {
+                s.pierce -= 1;
+                self.life -= s.damage;
+                game.shake += 1;
+                game.bg.chaos(&mut game.rng);
+                game.events.push(GameEvent::EnemyHit);
+
+                let angle = Normal::new(s.vel.angle() as f64, 40.0).unwrap();
+                let speed = Normal::new(60.0, 12.0).unwrap();
+                for _ in 0..=s.damage {
+                    game.particles.push(Particle {
+                        pos: s.pos,
+                        speed: speed.sample(&mut game.rng) as f32,
+                        damp: 0.8,
+                        angle: angle.sample(&mut game.rng) as f32,
+                        shape: Shape::Shard(0.2, 3.0, true),
+                        color: Color::WHITE.with_alpha(0.8),
+                        ..Particle::default()
+                    })
+                }
+            }
+        }
+    }
+
+    pub fn particles(&self, rng: &mut XorShiftRng) -> Vec<Particle> {
+        let angle = Uniform::new(0.0, 360.0);
+        let speed = 12.0;
+        let acc = -speed * speed / (speed + 2.0 * self.radius);
+
+        (0..6).map(|_| Particle {
+            pos: self.pos,
+            speed,
+            angle: angle.sample(rng),
+            accel: acc,
+            shape: Shape::Circle(4.0),
+            color: self.color(),
+            ..Particle::default()
+        }).collect()
+    }
+
+    /// The big explosion when it dies
+    pub fn explosion(&self, rng: &mut XorShiftRng) -> Vec<Particle> {
+        let angle = Uniform::new(0.0, 360.0);
+        let speed = Normal::new(70.0, 20.0).unwrap();
+
+        (0..80).map(|_| Particle {
+            pos: self.pos,
+            speed: speed.sample(rng) as f32,
+            damp: 0.85,
+            angle: angle.sample(rng),
+            shape: Shape::Shard(0.3, 3.0, true),
+            color: self.color(),
+            ..Particle::default()
+        }).collect()
+    }
+}
@@ -0,0 +1,102 @@
+//! The part of the arena that is on screen.
+//!
+//! When the arena is as big as the screen the camera never moves. In a
+//! larger arena it follows the player, a bit late so it feels smooth, and
+//! stops at the edges of the arena. It is part of the simulation, as the
+//! mouse is given in screen coordinates and the camera turns it into a
+//! position in the arena, so replays have to see the same camera.
+
+use quicksilver::geom::{Rectangle, Vector};
+use serde::{Serialize, Deserialize};
+
+use super::SIZE;
+
+/// How much of the way to its target the camera moves each tick
+const FOLLOW: f32 = 0.12;
+/// Wider or higher arenas need too many cells in the collision grids
+const MAX_ARENA: f32 = 20_000.0;
+
+/// A world larger than the screen, for the scrolling arena mode.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BigArena {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for BigArena {
+    fn default() -> Self {
+        BigArena {
+            width: 3200.0,
+            height: 2000.0,
+        }
+    }
+}
+
+impl BigArena {
+    pub fn size(&self) -> Vector {
+        Vector::new(self.width, self.height).max(SIZE)
+    }
+
+    /// Arenas smaller than the screen are fine, they are made bigger
+    pub fn check(&self) -> Result<(), String> {
+        if !(self.width <= MAX_ARENA && self.height <= MAX_ARENA) {
+            return Err(format!("arena: the size must be at most {}", MAX_ARENA));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Camera {
+    /// The top left of the view, in the arena
+    pub pos: Vector,
+    /// Where it was at the start of the tick, to draw in between
+    pub prev: Vector,
+    /// The size of the arena it moves in
+    arena: Vector,
+}
+
+impl Camera {
+    /// Looking at `target` right away
+    pub fn new(target: Vector, arena: Vector) -> Self {
+        let mut camera = Camera {
+            pos: Vector::ZERO,
+            prev: Vector::ZERO,
+            arena,
+        };
+        camera.pos = camera.goal(target);
+        camera.prev = camera.pos;
+        camera
+    }
+
+    /// Where the view would be centred on `target`, without going out of the arena
+    fn goal(&self, target: Vector) -> Vector {
+        (target - SIZE / 2.0).clamp(Vector::ZERO, self.arena - SIZE)
+    }
+
+    /// Move a step towards `target`
+    pub fn follow(&mut self, target: Vector) {
+        self.pos += (self.goal(target) - self.pos) * FOLLOW;
+    }
+
+    /// Where it is `prop` of the way between the last tick and the next
+    pub fn lerp(&self, prop: f32) -> Vector {
+        self.prev + (self.pos - self.prev) * prop
+    }
+
+    /// What is on screen
+    pub fn view(&self) -> Rectangle {
+        Rectangle::new(self.pos, SIZE)
+    }
+
+    /// A point of the screen, in the arena
+    pub fn to_world(&self, screen: Vector) -> Vector {
+        screen + self.pos
+    }
+
+    /// Whether `pos` is on screen, or less than `margin` from its edges
+    pub fn sees(&self, pos: Vector, margin: f32) -> bool {
+        let rel = pos - self.pos;
+        rel.x > -margin && rel.y > -margin && rel.x < SIZE.x + margin && rel.y < SIZE.y + margin
+    }
+}
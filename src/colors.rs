@@ -26,7 +26,7 @@ pub fn hsv2rgb(h: f32, s: f32, v: f32) -> Color
         2 => (p, v, t),
         3 => (p, q, v),
         4 => (t, p, v),
-        5 | _ => (v, p, q),
+        _ => (v, p, q),
     };
 
     Color { r, g, b, a: 1.0 }
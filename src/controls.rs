@@ -0,0 +1,285 @@
+//! Control schemes, and the keys they use.
+//!
+//! Whatever the scheme, the inputs of a tick end up in a `Controls`: where
+//! the pointer is, and which way the keys held push the movement and the
+//! aim. The game only sees that, and how it is used depends on the scheme
+//! of the rules, so replays do not need to know about keys at all.
+
+use std::collections::HashMap;
+
+use quicksilver::{
+    geom::Vector,
+    input::Key,
+};
+use serde::{Serialize, Deserialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// The player goes towards the mouse and fires where it clicks
+    #[default]
+    MouseFollow,
+    /// Keys to move, the mouse to aim
+    KeysAndMouse,
+    /// Keys to move, other keys to aim
+    KeysOnly,
+}
+
+impl ControlScheme {
+    pub const ALL: [ControlScheme; 3] = [ControlScheme::MouseFollow, ControlScheme::KeysAndMouse, ControlScheme::KeysOnly];
+
+    /// The scheme after this one, to cycle through them
+    pub fn next(self) -> Self {
+        let i = ControlScheme::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        ControlScheme::ALL[(i + 1) % ControlScheme::ALL.len()]
+    }
+
+    /// Whether the pointer says where to fire
+    pub fn aims_with_mouse(self) -> bool {
+        self != ControlScheme::KeysOnly
+    }
+}
+
+/// What the player does with the controls during one tick.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Controls {
+    /// Where the pointer is on screen
+    pub pointer: Vector,
+    /// The keys held to move, each coordinate in -1..=1
+    pub movement: Vector,
+    /// The keys held to aim, each coordinate in -1..=1
+    pub aim: Vector,
+    /// Whether the fire key or the mouse button is held
+    pub fire: bool,
+    /// Whether the dash key or the right mouse button is held
+    pub dash: bool,
+}
+
+impl From<Vector> for Controls {
+    /// Only the mouse
+    fn from(pointer: Vector) -> Self {
+        Controls {
+            pointer,
+            ..Controls::default()
+        }
+    }
+}
+
+/// What a key can be bound to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Command {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    AimUp,
+    AimDown,
+    AimLeft,
+    AimRight,
+    Fire,
+    Pause,
+    /// Draw the offered powerups again
+    Reroll,
+    Dash,
+    /// Show or hide the stat sheet
+    Stats,
+}
+
+impl Command {
+    pub const ALL: [Command; 13] = [
+        Command::MoveUp,
+        Command::MoveDown,
+        Command::MoveLeft,
+        Command::MoveRight,
+        Command::AimUp,
+        Command::AimDown,
+        Command::AimLeft,
+        Command::AimRight,
+        Command::Fire,
+        Command::Pause,
+        Command::Reroll,
+        Command::Dash,
+        Command::Stats,
+    ];
+
+    fn default_key(self) -> Key {
+        match self {
+            Command::MoveUp => Key::W,
+            Command::MoveDown => Key::S,
+            Command::MoveLeft => Key::A,
+            Command::MoveRight => Key::D,
+            Command::AimUp => Key::Up,
+            Command::AimDown => Key::Down,
+            Command::AimLeft => Key::Left,
+            Command::AimRight => Key::Right,
+            Command::Fire => Key::Space,
+            Command::Pause => Key::P,
+            Command::Reroll => Key::R,
+            Command::Dash => Key::LShift,
+            Command::Stats => Key::Tab,
+        }
+    }
+}
+
+macro_rules! bindable_keys {
+    ($($key:ident),* $(,)?) => {
+        /// The keys that can be bound, with the names they are saved under
+        const BINDABLE: &[(Key, &str)] = &[$((Key::$key, stringify!($key))),*];
+    };
+}
+
+bindable_keys!(
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9,
+    Up, Down, Left, Right, Space, Return, Tab, Back,
+    LShift, RShift, LControl, RControl, LAlt, RAlt,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    Comma, Period, Semicolon, Slash, Minus, Equals, Apostrophe, LBracket, RBracket,
+);
+
+/// The name of a key, for the menus and the settings file
+pub fn key_name(key: Key) -> Option<&'static str> {
+    BINDABLE.iter().find(|b| b.0 == key).map(|b| b.1)
+}
+
+fn key_named(name: &str) -> Option<Key> {
+    BINDABLE.iter().find(|b| b.1 == name).map(|b| b.0)
+}
+
+/// Which key does what. Saved with the key names, and the keys that
+/// can not be read back keep their default. A key is never bound twice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "HashMap<Command, String>", into = "HashMap<Command, String>")]
+pub struct Bindings {
+    keys: HashMap<Command, Key>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            keys: Command::ALL.iter().map(|&c| (c, c.default_key())).collect(),
+        }
+    }
+}
+
+impl From<HashMap<Command, String>> for Bindings {
+    fn from(names: HashMap<Command, String>) -> Self {
+        let mut keys: HashMap<Command, Key> = HashMap::new();
+        let taken = |keys: &HashMap<Command, Key>, key: Key| keys.values().any(|&k| k == key);
+
+        // The saved keys first, then the defaults of the commands the file
+        // does not have, and a free key when another command took it.
+        let bind = |keys: &mut HashMap<Command, Key>, command: Command, key: Option<Key>| {
+            if let Some(key) = key.filter(|&k| !taken(keys, k)) {
+                keys.entry(command).or_insert(key);
+            }
+        };
+        for &command in &Command::ALL {
+            bind(&mut keys, command, names.get(&command).and_then(|name| key_named(name)));
+        }
+        for &command in &Command::ALL {
+            bind(&mut keys, command, Some(command.default_key()));
+        }
+        for &command in &Command::ALL {
+            let free = BINDABLE.iter().map(|b| b.0).find(|&k| !taken(&keys, k));
+            bind(&mut keys, command, free);
+        }
+        Bindings { keys }
+    }
+}
+
+impl From<Bindings> for HashMap<Command, String> {
+    fn from(bindings: Bindings) -> Self {
+        bindings.keys.iter()
+            .filter_map(|(&c, &k)| Some((c, key_name(k)?.to_string())))
+            .collect()
+    }
+}
+
+impl Bindings {
+    pub fn key(&self, command: Command) -> Key {
+        self.keys[&command]
+    }
+
+    /// Bind `key` to `command`. A key does one thing only, so the command
+    /// it was bound to gets the previous key of `command`. Returns false
+    /// when the key can not be bound.
+    pub fn bind(&mut self, command: Command, key: Key) -> bool {
+        if key_name(key).is_none() {
+            return false;
+        }
+        let previous = self.key(command);
+        if let Some((&other, _)) = self.keys.iter().find(|(_, &k)| k == key) {
+            self.keys.insert(other, previous);
+        }
+        self.keys.insert(command, key);
+        true
+    }
+
+    /// The command of a key, if it has one
+    pub fn command(&self, key: Key) -> Option<Command> {
+        self.keys.iter().find(|(_, &k)| k == key).map(|(&c, _)| c)
+    }
+
+    /// The controls for a tick, `held` telling which keys are down
+    pub fn controls(&self, pointer: Vector, held: impl Fn(Key) -> bool) -> Controls {
+        let axis = |minus: Command, plus: Command| {
+            held(self.key(plus)) as i32 as f32 - held(self.key(minus)) as i32 as f32
+        };
+        Controls {
+            pointer,
+            movement: Vector::new(
+                axis(Command::MoveLeft, Command::MoveRight),
+                axis(Command::MoveUp, Command::MoveDown),
+            ),
+            aim: Vector::new(
+                axis(Command::AimLeft, Command::AimRight),
+                axis(Command::AimUp, Command::AimDown),
+            ),
+            fire: held(self.key(Command::Fire)),
+            dash: held(self.key(Command::Dash)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(keys: &[(Command, &str)]) -> Bindings {
+        keys.iter().map(|&(c, k)| (c, k.to_string())).collect::<HashMap<_, _>>().into()
+    }
+
+    fn unique(bindings: &Bindings) -> bool {
+        Command::ALL.iter().all(|&c| bindings.command(bindings.key(c)) == Some(c))
+    }
+
+    #[test]
+    fn old_settings_keep_their_keys() {
+        // Saved before Reroll, Dash and Stats existed, with their default keys taken
+        let bindings = saved(&[(Command::Fire, "R"), (Command::Pause, "LShift"), (Command::MoveUp, "Tab")]);
+        assert!(unique(&bindings));
+        assert_eq!(bindings.key(Command::Fire), Key::R);
+        assert_eq!(bindings.key(Command::Pause), Key::LShift);
+        assert_eq!(bindings.key(Command::MoveUp), Key::Tab);
+        assert_ne!(bindings.key(Command::Reroll), Key::R);
+        assert_eq!(bindings.key(Command::MoveDown), Key::S);
+    }
+
+    #[test]
+    fn a_key_saved_twice_is_bound_once() {
+        let bindings = saved(&[(Command::Fire, "Q"), (Command::Pause, "Q"), (Command::Dash, "Nope")]);
+        assert!(unique(&bindings));
+        assert_eq!(bindings.key(Command::Fire), Key::Q);
+        assert_eq!(bindings.key(Command::Pause), Key::P);
+        assert_eq!(bindings.key(Command::Dash), Key::LShift);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut bindings = Bindings::default();
+        assert!(bindings.bind(Command::Fire, Key::W));
+        assert_eq!(bindings.key(Command::MoveUp), Key::Space);
+        let json = serde_json::to_string(&bindings).unwrap();
+        assert_eq!(serde_json::from_str::<Bindings>(&json).unwrap(), bindings);
+    }
+}
@@ -0,0 +1,178 @@
+//! The powerups offered between the waves.
+//!
+//! The offers are drawn from the pool of the wave, the rarer powers less
+//! often, with the rng of the run so a replay gets the same ones. The
+//! player can draw them again while they have reroll tokens.
+
+use quicksilver::graphics::Color;
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+
+use super::{XorShiftRng, Power};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rarity {
+    Common,
+    Rare,
+    Epic,
+}
+
+impl Rarity {
+    /// How likely it is to be drawn, against the others
+    pub fn weight(self) -> u32 {
+        match self {
+            Rarity::Common => 6,
+            Rarity::Rare => 3,
+            Rarity::Epic => 1,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Rarity::Common => Color::WHITE,
+            Rarity::Rare => Color::from_hex("#59ABE3"),
+            Rarity::Epic => Color::from_hex("#F7CA18"),
+        }
+    }
+}
+
+impl Power {
+    pub fn rarity(self) -> Rarity {
+        match self {
+            Power::LifeUp | Power::DamageUp | Power::Ricochet | Power::SlowMo
+            | Power::Magnet | Power::BigShots
+            | Power::RapidFire | Power::Berserk | Power::Freeze => Rarity::Common,
+            Power::PierceUp | Power::FireRate | Power::Homing | Power::Shield | Power::Dash => Rarity::Rare,
+            Power::ShotUp | Power::ChainLightning => Rarity::Epic,
+        }
+    }
+}
+
+/// How the offers are made, in the wave script.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Draft {
+    /// Drawn after each wave, at least as many as the picks
+    pub offers: usize,
+    /// Reroll tokens at the start of a run
+    pub rerolls: u32,
+}
+
+impl Default for Draft {
+    fn default() -> Self {
+        Draft {
+            offers: 4,
+            rerolls: 0,
+        }
+    }
+}
+
+/// Up to `n` different powers of the `pool`, weighted by their rarity
+pub fn draw(pool: &[Power], n: usize, rng: &mut XorShiftRng) -> Vec<Power> {
+    let mut pool = pool.to_vec();
+    let mut offers = vec![];
+    while offers.len() < n {
+        let p = match pool.choose_weighted(rng, |p| p.rarity().weight()) {
+            Ok(&p) => p,
+            Err(_) => break,
+        };
+        pool.retain(|&q| q != p);
+        offers.push(p);
+    }
+    offers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{RngCore, SeedableRng};
+    use crate::{Game, WaveState, Controls};
+
+    const POOL: [Power; 5] = [Power::ShotUp, Power::DamageUp, Power::PierceUp, Power::LifeUp, Power::DamageUp];
+
+    #[test]
+    fn same_seed_same_offers() {
+        for seed in 0..20 {
+            let draw_with = |seed| draw(&POOL, 3, &mut XorShiftRng::seed_from_u64(seed));
+            assert_eq!(draw_with(seed), draw_with(seed));
+        }
+    }
+
+    #[test]
+    fn never_twice() {
+        let mut rng = XorShiftRng::seed_from_u64(0);
+        for n in 0..8 {
+            let offers = draw(&POOL, n, &mut rng);
+            assert_eq!(offers.len(), n.min(4));
+            for p in &offers {
+                assert_eq!(offers.iter().filter(|&q| q == p).count(), 1, "{:?} in {:?}", p, offers);
+                assert!(POOL.contains(p));
+            }
+        }
+        assert!(draw(&[], 3, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn rarer_less_often() {
+        let pool = [Power::ShotUp, Power::LifeUp];
+        assert_eq!(Power::ShotUp.rarity(), Rarity::Epic);
+        assert_eq!(Power::LifeUp.rarity(), Rarity::Common);
+
+        let epic = (0..1000)
+            .filter(|&seed| draw(&pool, 1, &mut XorShiftRng::seed_from_u64(seed)) == [Power::ShotUp])
+            .count();
+        // One in seven with the weights
+        assert!((90..200).contains(&epic), "{} epic draws out of 1000", epic);
+    }
+
+    /// A game where the first wave is over and its powerups are offered
+    fn offering(rerolls: u32) -> Game {
+        let mut game = Game::new();
+        game.player.rerolls = rerolls;
+        game.wave_state = WaveState::AnnoncePowerUp(0);
+        game.update(Controls::default());
+        assert_eq!(game.wave_state, WaveState::PowerUp);
+        game
+    }
+
+    fn offers(game: &Game) -> Vec<Power> {
+        game.powerups.iter().filter(|p| p.offer).map(|p| p.power).collect()
+    }
+
+    #[test]
+    fn reroll() {
+        let mut game = offering(2);
+        let picks = game.picks_left;
+        assert!(picks > 0);
+
+        let mut seen = vec![offers(&game)];
+        for left in [1, 0] {
+            game.reroll();
+            assert_eq!(game.player.rerolls, left);
+            assert_eq!(game.picks_left, picks);
+            assert_eq!(offers(&game).len(), seen[0].len());
+            seen.push(offers(&game));
+        }
+
+        // No token left
+        let mut before = game.rng.clone();
+        game.reroll();
+        assert_eq!(offers(&game), seen[2]);
+        assert_eq!(game.rng.next_u64(), before.next_u64());
+
+        // The same run draws the same offers
+        let mut again = offering(2);
+        again.reroll();
+        assert_eq!(offers(&again), seen[1]);
+    }
+
+    #[test]
+    fn no_reroll_during_a_wave() {
+        let mut game = Game::new();
+        game.player.rerolls = 2;
+        game.update(Controls::default());
+        game.reroll();
+        assert_eq!(game.player.rerolls, 2);
+        assert!(offers(&game).is_empty());
+    }
+}
@@ -1,37 +1,130 @@
 use quicksilver::{
-    geom::{Vector, Circle},
-    graphics::{Color, Graphics},
+    geom::Vector,
+    graphics::Color,
 };
 use rand_distr::*;
 use rand_xorshift::XorShiftRng;
+use serde::{Serialize, Deserialize};
 
-use super::{Particle, Shape, Game};
+use super::{Particle, Shape, Game, Faction, Shot, GameEvent, Power, Status, Statuses, Evolution};
+use crate::powers::Zap;
 
 const KNOCK_BACK: f32 = 50.0;
 const KNOCK_DAMP: f32 = 0.8;
 const INVINCIBLE_FRAMES: f32 = 4.0;
-const DIE_SHARDS: usize = 3;
 
+/// Frames before a ranged enemy fires, or a dasher dashes, for the first time
+const FIRST_TIMER: u32 = 60;
+/// Snipers back off when the player is closer than this
+const SNIPER_RANGE: f32 = 400.0;
+const SNIPER_RELOAD: u32 = 90;
+const SNIPER_BULLET_SPEED: f32 = 12.0;
+const SPITTER_RELOAD: u32 = 120;
+const SPITTER_BULLET_SPEED: f32 = 6.0;
+const DASH_SPEED: f32 = 22.0;
+const DASH_RELOAD: u32 = 50;
+/// How fast the speed goes back to normal after a dash
+const DASH_DAMP: f32 = 0.92;
+/// Armored enemies only take damage from shots less than this many degrees from their back
+pub const ARMOR_OPENING: f32 = 70.0;
+/// Kamikazes explode when the gap between them and the player is smaller than this
+const KAMIKAZE_FUSE: f32 = 40.0;
+const KAMIKAZE_BULLETS: u32 = 8;
+const KAMIKAZE_BULLET_SPEED: f32 = 7.0;
+/// Angle between the children of an enemy that splits
+const SPLIT_SPREAD: f32 = 60.0;
+/// Ticks between two burns, that take a life for each stack
+pub const BURN_PERIOD: u32 = 15;
+/// How much slower it goes for each stack of freeze
+const FREEZE_SLOW: f32 = 0.25;
+/// More jumps for the lightning once it evolved in a storm
+const STORM_JUMPS: u32 = 2;
+
+/// What an enemy looks and behaves like, on top of its level.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnemyKind {
+    /// Follows the player and splits in two when killed
+    Slime,
+    /// Keeps its distance and fires aimed bullets
+    Sniper,
+    /// Slowly comes closer and fires rings of bullets
+    Spitter,
+    /// Small and frail, but rushes at the player every now and then
+    Dasher,
+    /// Slow, tough and turns slowly, only shots in its back hurt it
+    Armored,
+    /// Like a slime, but splits in three
+    Splitter,
+    /// Rushes at the player and explodes in a ring of bullets, also when killed
+    Kamikaze,
+}
+
+impl EnemyKind {
+    fn max_speed(self, level: u32) -> f32 {
+        let level = level as f32;
+        match self {
+            EnemyKind::Slime | EnemyKind::Splitter => 4.0 + level,
+            EnemyKind::Sniper | EnemyKind::Spitter | EnemyKind::Dasher => 2.0 + level * 0.5,
+            EnemyKind::Armored => 1.5 + level * 0.3,
+            EnemyKind::Kamikaze => 6.0 + level,
+        }
+    }
+
+    /// How much of the angle to its heading it turns each frame
+    fn turn(self) -> f32 {
+        match self {
+            EnemyKind::Armored => 0.03,
+            EnemyKind::Kamikaze => 0.15,
+            _ => 0.09,
+        }
+    }
+
+    /// How many enemies of one level less it splits into when killed
+    fn children(self) -> u32 {
+        match self {
+            EnemyKind::Slime => 2,
+            EnemyKind::Splitter => 3,
+            _ => 0,
+        }
+    }
+
+    fn particle_shape(self) -> Shape {
+        match self {
+            EnemyKind::Armored => Shape::Circle(5.0),
+            EnemyKind::Splitter => Shape::Circle(2.0),
+            EnemyKind::Dasher | EnemyKind::Kamikaze => Shape::Shard(0.5, 3.0, true),
+            _ => Shape::Circle(3.0),
+        }
+    }
+}
 
 #[derive(Copy, Clone, Debug)]
 pub struct Enemy {
+    pub kind: EnemyKind,
     pub pos: Vector,
     pub speed: f32,
     pub angle: f32,
     pub radius: f32,
     pub level: u32,
-    life: i32,
+    pub life: i32,
     pub knockback: Vector,
-    color: Color,
+    pub color: Color,
+    /// Frames until its next move: firing for the ranged kinds, dashing for the dasher
+    pub timer: u32,
+    /// Where the last hit was going, its children go apart from it
+    hit_angle: Option<f32>,
+    pub statuses: Statuses,
+    /// It blew itself up on the player, nobody killed it
+    pub detonated: bool,
 }
 
 impl Enemy {
-    pub fn new(pos: Vector, level: u32) -> Self {
-        Enemy::new_kb(pos, level, Vector::ZERO)
+    pub fn new(kind: EnemyKind, pos: Vector, level: u32) -> Self {
+        Enemy::new_kb(kind, pos, level, Vector::ZERO)
     }
 
-    pub fn new_kb(pos: Vector, level: u32, knockback: Vector) -> Self {
-        let colors = vec![
+    pub fn new_kb(kind: EnemyKind, pos: Vector, level: u32, knockback: Vector) -> Self {
+        let colors = [
             Color::PURPLE,
             Color::INDIGO,
             Color::MAGENTA,
@@ -40,17 +133,32 @@ impl Enemy {
             Color::ORANGE,
             Color::RED,
         ];
-        let color = colors[level as usize % colors.len()];
+        let palette = colors[level as usize % colors.len()];
+
+        let (life, radius, color) = match kind {
+            EnemyKind::Slime | EnemyKind::Sniper | EnemyKind::Spitter =>
+                (level * level, level as f32 * 5.0 + 30.0, palette),
+            EnemyKind::Dasher => (level, level as f32 * 3.0 + 20.0, Color::YELLOW),
+            EnemyKind::Armored =>
+                (2 * level * level, level as f32 * 5.0 + 40.0, Color::from_rgba(170, 170, 190, 1.0)),
+            EnemyKind::Splitter => (level * level, level as f32 * 5.0 + 30.0, Color::CYAN),
+            EnemyKind::Kamikaze => (level, level as f32 * 3.0 + 20.0, Color::RED),
+        };
 
         Enemy {
-            pos: pos,
+            kind,
+            pos,
             speed: 0.0,
             angle: 0.0,
-            level: level,
-            life: (level * level) as i32,
-            radius: level as f32 * 5.0 + 30.0 ,
-            knockback: knockback,
-            color: color,
+            level,
+            life: life as i32,
+            radius,
+            knockback,
+            color,
+            timer: FIRST_TIMER,
+            hit_angle: None,
+            statuses: Statuses::default(),
+            detonated: false,
         }
     }
     
@@ -61,77 +169,235 @@ impl Enemy {
         self.knockback.len2() >= KNOCK_BACK * KNOCK_DAMP.powf(INVINCIBLE_FRAMES) * 0.99  // rounding
     }
 
-    pub fn update(&mut self, game: &mut Game) -> Vec<Self> {
+    /// `heading` is where the enemy wants to go, the player
+    /// unless it is flocking with others.
+    pub fn update(&mut self, game: &mut Game, heading: Vector) -> Vec<Self> {
 
         // Move and update speed + angle
-        let player_dir = game.player.pos - self.pos;
-        let player_angle = player_dir.angle();
+        let to_player = game.player.pos - self.pos;
+        let heading = if self.kind == EnemyKind::Sniper && to_player.len2() < SNIPER_RANGE.powi(2) {
+            -heading
+        } else {
+            heading
+        };
 
-        let angular_diff = ((player_angle - self.angle) % 360.0 + 540.0) % 360.0 - 180.0;
-        self.angle = (self.angle + 0.09 * angular_diff) % 360.0;
+        let angular_diff = angle_diff(heading.angle(), self.angle);
+        self.angle = (self.angle + self.kind.turn() * angular_diff) % 360.0;
 
-        self.speed = (self.speed + 0.4).min(4.0 + (self.level as f32));
+        let max_speed = self.kind.max_speed(self.level);
+        self.speed = if self.speed > max_speed {
+            // Slowing down after a dash
+            (self.speed * DASH_DAMP).max(max_speed)
+        } else {
+            (self.speed + 0.4).min(max_speed)
+        };
          
-        let vel = Vector::from_angle(self.angle) * self.speed;
+        let frozen = 1.0 - FREEZE_SLOW * self.statuses.stacks(Status::Freeze) as f32;
+        let vel = Vector::from_angle(self.angle) * self.speed * game.time_scale() * frozen;
         self.pos += vel;
 
         self.knockback *= KNOCK_DAMP;
-        self.pos += self.knockback;
+        self.pos += self.knockback * game.time_scale();
+
+        self.special(game, to_player);
+        self.burn(game);
+        self.statuses.tick();
 
         // Check collisions
-        let mut hit_angle = None;
-        if !self.invincible() {
-            for s in &mut game.shots {
-                if s.pierce > 0 && (s.pos - self.pos).len2() < (s.radius + self.radius).powi(2) {
+        if self.alive() && !self.invincible() {
+            for i in game.grids.shots.query(self.pos, self.radius) {
+                let s = &mut game.shots[i];
+                if s.owner == Faction::Player && s.pierce > 0
+                    && (s.pos - self.pos).len2() < (s.radius + self.radius).powi(2) {
                     s.pierce -= 1;
-                    let dmg = s.damage.min(self.life);
-                    self.life -= dmg;
-
                     let a = s.vel.angle();
-                    hit_angle = Some(a);
-                    
-                    self.knockback = Vector::from_angle(a) * KNOCK_BACK;
-
-                    game.shake += 1;
-                    game.bg.chaos(&mut game.rng);
-
-
-                    let angle = Normal::new(a as f64, 40.0).unwrap();
-                    let speed = Normal::new(60.0, 12.0).unwrap();
-                    for _ in 0..=dmg {
-                        // let angle = 360.0 * (i as f32) / (DIE_SHARDS as f32);
-                        game.particles.push(Particle {
-                            pos: self.pos,
-                            speed: speed.sample(&mut game.rng) as f32,
-                            damp: 0.8,
-                            angle: angle.sample(&mut game.rng) as f32,
-                            shape: Shape::Shard(0.2, 3.0, true),
-                            color: Color::WHITE.with_alpha(0.8),
-                            ..Particle::default()
-                        })
+
+                    if self.kind == EnemyKind::Armored && angle_diff(a, self.angle).abs() > ARMOR_OPENING {
+                        // Bounces on the armor
+                        let angle = Normal::new(a as f64 + 180.0, 30.0).unwrap();
+                        for _ in 0..3 {
+                            game.particles.push(Particle {
+                                pos: s.pos,
+                                speed: 30.0,
+                                damp: 0.7,
+                                angle: angle.sample(&mut game.rng) as f32,
+                                shape: Shape::Shard(0.2, 3.0, true),
+                                color: self.color,
+                                ..Particle::default()
+                            })
+                        }
+                        continue;
+                    }
+
+                    let (damage, burns) = (s.damage, s.burns);
+                    self.hurt(game, damage, a);
+                    if burns {
+                        self.statuses.apply(Status::Burn);
+                    }
+                    if game.player.chain > 0 {
+                        let storm = if game.player.evolved(Evolution::Storm) { STORM_JUMPS } else { 0 };
+                        game.zaps.push(Zap { from: self.pos, jumps: game.player.chain + storm });
                     }
                 }
             }
         }
 
-        if !self.alive() && self.level > 1 {
-            let d = hit_angle.unwrap();
-            let dir1 = Vector::from_angle(d + 30.0) * KNOCK_BACK;
-            let dir2 = Vector::from_angle(d - 30.0) * KNOCK_BACK;
-            vec![
-                Enemy::new_kb(self.pos, self.level - 1, dir1),
-                Enemy::new_kb(self.pos, self.level - 1, dir2),
-            ]
+        if self.alive() {
+            return vec![];
+        }
+        if self.kind == EnemyKind::Kamikaze {
+            self.explode(game);
+        }
+
+        let children = self.kind.children();
+        match self.hit_angle {
+            Some(d) if self.level > 1 => (0..children).map(|i| {
+                let a = d + (i as f32 - (children - 1) as f32 / 2.0) * SPLIT_SPREAD;
+                Enemy::new_kb(self.kind, self.pos, self.level - 1, Vector::from_angle(a) * KNOCK_BACK)
+            }).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Lose life to a hit going towards `angle`. It dies in `update`.
+    pub fn hurt(&mut self, game: &mut Game, damage: i32, angle: f32) {
+        let dmg = damage.min(self.life);
+        self.life -= dmg;
+        self.hit_angle = Some(angle);
+        self.knockback = Vector::from_angle(angle) * KNOCK_BACK;
+
+        game.shake += 1;
+        game.bg.chaos(&mut game.rng);
+        game.events.push(GameEvent::EnemyHit);
+
+        let angle = Normal::new(angle as f64, 40.0).unwrap();
+        let speed = Normal::new(60.0, 12.0).unwrap();
+        for _ in 0..=dmg {
+            game.particles.push(Particle {
+                pos: self.pos,
+                speed: speed.sample(&mut game.rng) as f32,
+                damp: 0.8,
+                angle: angle.sample(&mut game.rng) as f32,
+                shape: Shape::Shard(0.2, 3.0, true),
+                color: Color::WHITE.with_alpha(0.8),
+                ..Particle::default()
+            })
+        }
+    }
+
+    /// Lose a life for each stack every few ticks while burning
+    fn burn(&mut self, game: &mut Game) {
+        let frames = self.statuses.frames(Status::Burn);
+        if frames == 0 || !frames.is_multiple_of(BURN_PERIOD) || !self.alive() {
+            return;
+        }
+        self.life -= (self.statuses.stacks(Status::Burn) as i32).min(self.life);
+        self.hit_angle.get_or_insert(self.angle);
+        game.events.push(GameEvent::EnemyHit);
+
+        let angle = Normal::new(-90.0, 30.0).unwrap();
+        for _ in 0..4 {
+            game.particles.push(Particle {
+                pos: self.pos,
+                speed: 20.0,
+                damp: 0.8,
+                angle: angle.sample(&mut game.rng) as f32,
+                shape: Shape::Shard(0.3, 3.0, true),
+                color: Color::ORANGE,
+                ..Particle::default()
+            })
+        }
+    }
+
+    /// Its color, or the one of what it suffers from
+    pub fn tint(&self) -> Color {
+        if self.statuses.has(Status::Burn) {
+            Color::ORANGE
+        } else if self.statuses.has(Status::Freeze) {
+            Power::Freeze.color()
         } else {
-            vec![]
+            self.color
         }
     }
 
-    pub fn draw(&self, gfx: &mut Graphics, prop: f32) {
-        gfx.fill_circle(
-            &Circle::new(self.pos, self.radius),
-            self.color.with_alpha(0.1)
-        );
+    /// What makes each kind special, apart from the way it moves.
+    fn special(&mut self, game: &mut Game, to_player: Vector) {
+        match self.kind {
+            EnemyKind::Kamikaze => {
+                if self.alive() && to_player.len() < self.radius + game.player.radius + KAMIKAZE_FUSE {
+                    self.life = 0;
+                    self.detonated = true;
+                }
+                return;
+            }
+            EnemyKind::Slime | EnemyKind::Armored | EnemyKind::Splitter => return,
+            _ => {}
+        }
+
+        if self.timer > 0 {
+            if game.clock_ticks() {
+                self.timer -= 1;
+            }
+            return;
+        }
+        // Not from off screen
+        if !game.camera.sees(self.pos, 50.0) {
+            return;
+        }
+
+        match self.kind {
+            EnemyKind::Sniper => {
+                let dir = if to_player.len2() > 0.0 { to_player.normalize() } else { Vector::X };
+                game.shots.push(Shot::hostile(
+                    self.pos + dir * self.radius,
+                    dir * SNIPER_BULLET_SPEED,
+                    self.color,
+                ));
+                self.timer = SNIPER_RELOAD;
+            }
+            EnemyKind::Spitter => {
+                self.ring(game, 5 + self.level, SPITTER_BULLET_SPEED);
+                self.timer = SPITTER_RELOAD;
+            }
+            EnemyKind::Dasher => {
+                self.angle = to_player.angle();
+                self.speed = DASH_SPEED;
+                self.timer = DASH_RELOAD;
+            }
+            _ => {}
+        }
+    }
+
+    /// Fire `bullets` bullets in all directions
+    fn ring(&self, game: &mut Game, bullets: u32, speed: f32) {
+        let offset = self.angle;
+        game.shots.extend((0..bullets).map(|i| {
+            let a = offset + 360.0 * i as f32 / bullets as f32;
+            Shot::hostile(
+                self.pos + Vector::from_angle(a) * self.radius,
+                Vector::from_angle(a) * speed,
+                self.color,
+            )
+        }));
+    }
+
+    fn explode(&self, game: &mut Game) {
+        self.ring(game, KAMIKAZE_BULLETS, KAMIKAZE_BULLET_SPEED);
+        game.shake += 4;
+
+        let angle = Uniform::new(0.0, 360.0);
+        let speed = Normal::new(40.0, 10.0).unwrap();
+        for _ in 0..20 {
+            game.particles.push(Particle {
+                pos: self.pos,
+                speed: speed.sample(&mut game.rng) as f32,
+                damp: 0.8,
+                angle: angle.sample(&mut game.rng),
+                shape: Shape::Shard(0.3, 3.0, true),
+                color: Color::ORANGE,
+                ..Particle::default()
+            })
+        }
     }
 
     pub fn particles(&self, rng: &mut XorShiftRng, density: f32) -> Vec<Particle> {
@@ -149,18 +415,21 @@ impl Enemy {
         let acc = -speed.powi(2) / (speed + 2.0 * self.radius);
         let bias = Vector::from_angle(self.angle) * self.speed;
 
-        let v = (0..qte).map(|_| Particle {
+        (0..qte).map(|_| Particle {
             pos: self.pos,
-            speed: speed,
+            speed,
             angle: angle.sample(rng),
             accel: acc,
-            bias: bias,
+            bias,
             // angular_vel: l * 0.1,
-            shape: Shape::Circle(3.0),
-            color: self.color,
+            shape: self.kind.particle_shape(),
+            color: self.tint(),
             ..Particle::default()
-        }).collect();
-
-        v
+        }).collect()
     }
-}
\ No newline at end of file
+}
+
+/// The signed difference between two angles in degrees, between -180 and 180
+pub fn angle_diff(a: f32, b: f32) -> f32 {
+    ((a - b) % 360.0 + 540.0) % 360.0 - 180.0
+}
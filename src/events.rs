@@ -0,0 +1,32 @@
+//! What happened in the game, for the sounds and anything else that reacts
+//! to the simulation without being part of it.
+
+use super::{EnemyKind, Power, Status, Evolution};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// The player fired a volley, or the lasers when hurt
+    ShotFired { laser: bool },
+    /// An enemy or the boss lost some life
+    EnemyHit,
+    /// An enemy died and left smaller ones behind
+    EnemySplit,
+    EnemyKilled(EnemyKind),
+    /// An enemy blew itself up on the player, it does not count as a kill
+    EnemyDetonated(EnemyKind),
+    BossKilled,
+    PlayerHurt,
+    PowerupTaken(Power),
+    /// The player started a dash
+    Dashed,
+    /// Lightning jumped from an enemy to another
+    Zapped,
+    /// A combination of powers unlocked an evolution
+    Evolved(Evolution),
+    /// A timed status of the player wore off
+    StatusEnded(Status),
+    /// Other powerups are offered, for a reroll token
+    Rerolled,
+    /// The enemies of this wave start to come
+    WaveStarted(u32),
+}
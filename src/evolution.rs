@@ -0,0 +1,78 @@
+//! Combinations of powers that change how they work.
+//!
+//! Once the player has taken all the powers a rule needs, the evolution
+//! stays for the rest of the run, and is announced on screen.
+
+use serde::{Serialize, Deserialize};
+
+use super::Power;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Evolution {
+    /// The shots are lasers
+    Lasers,
+    /// The volleys go all around the player
+    Ring,
+    /// Lightning jumps more
+    Storm,
+    /// The shield sends the bullets back
+    Reflector,
+}
+
+/// An evolution, and how many of each power it needs
+pub struct Rule {
+    pub evolution: Evolution,
+    pub needs: &'static [(Power, usize)],
+}
+
+pub const RULES: &[Rule] = &[
+    Rule { evolution: Evolution::Lasers, needs: &[(Power::PierceUp, 3), (Power::DamageUp, 2)] },
+    Rule { evolution: Evolution::Ring, needs: &[(Power::ShotUp, 5)] },
+    Rule { evolution: Evolution::Storm, needs: &[(Power::ChainLightning, 2), (Power::Homing, 1)] },
+    Rule { evolution: Evolution::Reflector, needs: &[(Power::Shield, 2), (Power::Ricochet, 1)] },
+];
+
+/// The evolutions the `powers` unlock that are not in `have` yet
+pub fn unlocked(powers: &[Power], have: &[Evolution]) -> Vec<Evolution> {
+    RULES.iter()
+        .filter(|r| !have.contains(&r.evolution))
+        .filter(|r| r.needs.iter().all(|&(p, n)| powers.iter().filter(|&&q| q == p).count() >= n))
+        .map(|r| r.evolution)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lasers() {
+        let mut powers = vec![Power::PierceUp, Power::PierceUp, Power::DamageUp, Power::DamageUp, Power::ShotUp];
+        assert!(unlocked(&powers, &[]).is_empty());
+        powers.push(Power::PierceUp);
+        assert_eq!(unlocked(&powers, &[]), vec![Evolution::Lasers]);
+        powers.push(Power::PierceUp);
+        assert_eq!(unlocked(&powers, &[]), vec![Evolution::Lasers]);
+    }
+
+    #[test]
+    fn only_once() {
+        let powers = [Power::PierceUp, Power::PierceUp, Power::PierceUp, Power::DamageUp, Power::DamageUp];
+        assert!(unlocked(&powers, &[Evolution::Lasers]).is_empty());
+    }
+
+    #[test]
+    fn each_rule_alone() {
+        for rule in RULES {
+            let powers: Vec<Power> = rule.needs.iter()
+                .flat_map(|&(p, n)| std::iter::repeat_n(p, n))
+                .collect();
+            assert_eq!(unlocked(&powers, &[]), vec![rule.evolution]);
+            for i in 0..powers.len() {
+                let mut missing = powers.clone();
+                missing.remove(i);
+                assert!(unlocked(&missing, &[]).is_empty(), "{:?} without {:?}", rule.evolution, powers[i]);
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Boids-like steering for the enemies.
+//!
+//! Each enemy wants to go towards the player, but also away from its
+//! closest neighbours (separation), in the same direction as them
+//! (alignment) and towards their center (cohesion).
+
+use quicksilver::geom::Vector;
+use serde::{Serialize, Deserialize};
+
+use super::{Enemy, EnemyKind, SpatialGrid};
+
+const MIN_RADIUS: f32 = 10.0;
+
+/// How much each rule counts for a kind of enemy.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Steering {
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+    pub seek: f32,
+}
+
+impl Default for Steering {
+    fn default() -> Self {
+        Steering {
+            separation: 1.5,
+            alignment: 0.4,
+            cohesion: 0.3,
+            seek: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Flocking {
+    /// How far away the neighbours are seen
+    pub radius: f32,
+    /// Kinds not listed here use the default steering
+    pub kinds: Vec<(EnemyKind, Steering)>,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Flocking {
+            radius: 150.0,
+            kinds: vec![(EnemyKind::Slime, Steering::default())],
+        }
+    }
+}
+
+impl Flocking {
+    /// The radius is also the cell size of the grid of neighbours,
+    /// which would have too many cells below `MIN_RADIUS`
+    pub fn check(&self) -> Result<(), String> {
+        if !(self.radius.is_finite() && self.radius >= MIN_RADIUS) {
+            return Err(format!("flocking: radius must be at least {}", MIN_RADIUS));
+        }
+        Ok(())
+    }
+
+    fn steering(&self, kind: EnemyKind) -> Steering {
+        self.kinds
+            .iter()
+            .find(|k| k.0 == kind)
+            .map_or_else(Steering::default, |k| k.1)
+    }
+
+    /// The direction each enemy wants to go to, in the same order.
+    pub fn headings(&self, enemies: &[Enemy], target: Vector, arena: Vector) -> Vec<Vector> {
+        let grid = SpatialGrid::build(self.radius, arena, enemies.iter().map(|e| (e.pos, 0.0)));
+
+        enemies.iter().enumerate().map(|(i, e)| {
+            let w = self.steering(e.kind);
+
+            let mut separation = Vector::ZERO;
+            let mut alignment = Vector::ZERO;
+            let mut center = Vector::ZERO;
+            let mut count = 0;
+
+            for j in grid.query(e.pos, self.radius) {
+                let other = &enemies[j];
+                let away = e.pos - other.pos;
+                let dist = away.len();
+                if j == i || dist > self.radius {
+                    continue;
+                }
+                count += 1;
+                alignment += Vector::from_angle(other.angle);
+                center += other.pos;
+
+                // Push harder when they overlap
+                let gap = (dist - e.radius - other.radius).max(1.0);
+                if dist > 0.0 {
+                    separation += away / dist * (e.radius / gap).min(3.0);
+                }
+            }
+
+            let mut heading = unit(target - e.pos) * w.seek;
+            if count > 0 {
+                let n = count as f32;
+                heading += separation * w.separation
+                    + unit(alignment / n) * w.alignment
+                    + unit(center / n - e.pos) * w.cohesion;
+            }
+            heading
+        }).collect()
+    }
+}
+
+/// The vector with a length of 1, or zero
+fn unit(v: Vector) -> Vector {
+    if v.len2() > 0.0 { v.normalize() } else { Vector::ZERO }
+}
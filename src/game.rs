@@ -0,0 +1,552 @@
+use quicksilver::geom::Vector;
+
+use rand::prelude::*;
+use rand_distr::*;
+use rand_xorshift::XorShiftRng;
+use std::mem::swap;
+
+use super::{ARENA, SIZE, Camera};
+use crate::particles::*;
+use crate::player::*;
+use crate::shot::*;
+use crate::enemy::*;
+use crate::background::*;
+use crate::overlay::*;
+use crate::powerup::*;
+use crate::replay::Action;
+use crate::controls::Controls;
+use crate::rules::Rules;
+use crate::waves::{WaveDef, EndCondition};
+use crate::boss::Boss;
+use crate::draft;
+use crate::powers::{self, Zap, SLOWMO_SCALE};
+use crate::status::Status;
+use crate::evolution::{self, Evolution};
+use crate::grid::Grids;
+use crate::events::GameEvent;
+
+
+const POWERUP_DELAY: u32 = 20;
+/// How long a new evolution is announced
+const EVOLVED_FRAMES: u32 = 90;
+pub const DEFAULT_SEED: u64 = 42;
+/// Seeds are kept short so they are easy to share
+pub const SEED_RANGE: u64 = 1_000_000;
+
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WaveState {
+    Ongoing,
+    WaitToEnd,
+    AnnoncePowerUp(u32),
+    PowerUp,
+    AnnounceWave(u32),
+    /// A boss wave, it ends when the boss dies
+    BossFight,
+}
+
+/// The whole state of a run.
+///
+/// Nothing in here needs a window: the game can be stepped with
+/// `update` and `action` from a test or a headless balance script,
+/// and the renderer only reads it.
+pub struct Game {
+    // Utilities
+    /// The seed the rng was created with
+    pub seed: u64,
+    pub rng: XorShiftRng,
+    pub rules: Rules,
+    // Entities
+    pub particles: Vec<Particle>,
+    pub player: Player,
+    pub enemies: Vec<Enemy>,
+    pub shots: Vec<Shot>,
+    pub powerups: Vec<PowerUp>,
+    pub boss: Option<Boss>,
+    /// Broad phase of the collisions
+    pub grids: Grids,
+    /// The size of the world
+    pub arena: Vector,
+    pub camera: Camera,
+    // General
+    pub frame: u32,
+    pub paused: bool,
+    pub score: u32,
+    pub shake: i32,
+    pub wave: u32,
+    pub wave_state: WaveState,
+    /// The script of the current wave
+    pub wave_def: WaveDef,
+    /// Frames spent and enemies killed since the wave started
+    pub wave_frames: u32,
+    pub wave_kills: u32,
+    /// How many of the offered powerups can still be taken
+    pub picks_left: usize,
+    pub bg: Background,
+    pub overlay: Overlay,
+    /// What happened during the last update, and the actions since.
+    /// Cleared at the start of each update.
+    pub events: Vec<GameEvent>,
+    /// Lightning from the hits of the last update
+    pub zaps: Vec<Zap>,
+    /// Ticks left in slow motion
+    pub slowmo: u32,
+    /// The last evolution, and the ticks left to announce it
+    pub evolved: Option<(Evolution, u32)>,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Game::with_seed(DEFAULT_SEED)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Game::with_rules(seed, Rules::default())
+    }
+
+    /// A run is fully determined by its seed, its rules and
+    /// the inputs given to `update` and `action`.
+    pub fn with_rules(seed: u64, rules: Rules) -> Self {
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+        let arena = rules.arena.map_or(ARENA, |a| a.size());
+        let mut player = Player::new();
+        player.rerolls = rules.waves.draft.rerolls;
+        if rules.arena.is_some() {
+            player.pos = arena / 2.0;
+        }
+        let mut g = Game {
+            bg: Background::new(&mut rng),
+            seed,
+            rng,
+            wave_def: rules.waves.wave(1),
+            rules,
+
+            particles: vec![],
+            camera: Camera::new(player.pos, arena),
+            player,
+            shots: vec![],
+            enemies: vec![],
+            powerups: vec![],
+            boss: None,
+            grids: Grids::new(arena),
+            arena,
+
+            paused: false,
+            wave: 0,
+            wave_state: WaveState::PowerUp,
+            wave_frames: 0,
+            wave_kills: 0,
+            picks_left: 0,
+            score: 0,
+            frame: 0,
+            shake: 0,
+            overlay: Overlay::pause(),
+            events: vec![],
+            zaps: vec![],
+            slowmo: 0,
+            evolved: None,
+        };
+        g.overlay.visible = false;
+
+        g
+    }
+
+    fn collect_particles(&mut self) {
+        // Update and remove dead particles
+        // We do it first so particles added this frame can
+        // be drawn where they spawn at least once
+        self.particles.retain_mut(|p| p.update());
+
+        self.particles.extend(self.overlay.particles(self.camera.pos));
+        for s in &self.shots {
+            self.particles.extend(s.particles(&mut self.rng));
+        }
+        self.particles.extend(self.player.particles(&mut self.rng));
+        let density = (200.0 / (50 + self.enemies.len()) as f32).max(0.3);
+        for e in &self.enemies {
+            self.particles.extend(e.particles(&mut self.rng, density));
+        }
+        for p in &self.powerups {
+            self.particles.extend(p.particles(&mut self.rng));
+        }
+        if let Some(b) = &self.boss {
+            self.particles.extend(b.particles(&mut self.rng));
+        }
+    }
+
+    /// Step the game, the pointer of the controls being on screen
+    pub fn update(&mut self, controls: Controls) {
+        let controls = Controls {
+            pointer: self.camera.to_world(controls.pointer),
+            ..controls
+        };
+        self.camera.prev = self.camera.pos;
+        self.events.clear();
+        self.bg.update(self.score);
+        self.collect_particles();
+
+        // The shakes used to wear off in the 60 fps draw loop,
+        // so they go twice as fast at the 30 Hz tick.
+        if self.shake > 0 {
+            self.shake = (self.shake - 2).clamp(0, 20);
+        }
+
+        if self.player.life == 0 { return; }
+        if self.paused { return; }
+
+        self.frame += 1;
+        self.evolved = self.evolved.and_then(|(e, t)| (t > 1).then_some((e, t - 1)));
+
+        self.wave_frames += 1;
+
+        self.wave_state = if self.wave_state == WaveState::Ongoing && self.wave_over() {
+            WaveState::WaitToEnd
+        } else if self.wave_state == WaveState::WaitToEnd && self.enemies.is_empty() {
+            self.overlay = Overlay::powerup(self.picks());
+            WaveState::AnnoncePowerUp(45)
+        } else if let WaveState::AnnoncePowerUp(t) = self.wave_state {
+            if t > 0 {
+                WaveState::AnnoncePowerUp(t-1)
+            } else {
+                self.overlay.visible = false;
+                self.offer_powerups();
+                WaveState::PowerUp
+            }
+        } else if self.wave_state == WaveState::PowerUp && self.picks_left == 0 {
+            self.wave += 1;
+            self.wave_def = self.rules.waves.wave(self.wave);
+            self.powerups = vec![];
+            self.overlay = if self.rules.waves.is_boss_wave(self.wave) {
+                Overlay::boss(self.wave)
+            } else {
+                Overlay::wave(self.wave)
+            };
+            WaveState::AnnounceWave(60)
+        } else if let WaveState::AnnounceWave(t) = self.wave_state {
+            if t > 0 {
+                WaveState::AnnounceWave(t-1)
+            } else {
+                self.overlay.visible = false;
+                self.wave_frames = 0;
+                self.wave_kills = 0;
+                self.events.push(GameEvent::WaveStarted(self.wave));
+                if self.rules.waves.is_boss_wave(self.wave) {
+                    self.spawn_boss();
+                    WaveState::BossFight
+                } else {
+                    WaveState::Ongoing
+                }
+            }
+        } else {
+            self.wave_state
+        };
+
+        if self.wave_state == WaveState::Ongoing {
+            // Spawn enemies and powerups if needed
+            self.spawn_enemy();
+            self.spawn_powerup();
+        } else if self.wave_state == WaveState::BossFight {
+            // The boss brings its own enemies
+            self.spawn_powerup();
+        }
+
+        // Update and remove shots
+        let slow = self.time_scale();
+        for s in &mut self.shots {
+            s.update(self.arena, if s.owner == Faction::Enemy { slow } else { 1.0 })
+        }
+        self.shots.retain(|s| s.alive);
+        self.grids.shots.rebuild(self.shots.iter().map(|s| (s.pos, s.radius)));
+
+        powers::update(self);
+
+        // Update and remove enemies
+        let headings = match &self.rules.flocking {
+            Some(flock) => flock.headings(&self.enemies, self.player.pos, self.arena),
+            None => self.enemies.iter().map(|e| self.player.pos - e.pos).collect(),
+        };
+        let mut enn = vec![];
+        let mut new_enn = vec![];
+        swap(&mut enn, &mut self.enemies);
+        self.enemies = enn
+            .iter()
+            .zip(headings)
+            .filter_map(|(e, heading)| {
+                let mut e = *e;
+                let children = e.update(self, heading);
+                if !children.is_empty() {
+                    self.events.push(GameEvent::EnemySplit);
+                }
+                new_enn.extend(children);
+
+                if e.alive() {
+                    Some(e)
+                } else if e.detonated {
+                    self.events.push(GameEvent::EnemyDetonated(e.kind));
+                    None
+                } else {
+                    self.events.push(GameEvent::EnemyKilled(e.kind));
+                    self.score += e.level * e.level;
+                    self.wave_kills += 1;
+                    None
+                }
+            })
+            .collect();
+        self.enemies.extend(new_enn);
+
+        // Update the boss
+        if let Some(mut boss) = self.boss.take() {
+            boss.update(self);
+            if boss.alive() {
+                self.boss = Some(boss);
+            } else {
+                self.boss_killed(&boss);
+            }
+        }
+
+        // Update and pick powerups
+        for p in &mut self.powerups {
+            p.update(self.arena);
+        }
+        self.grids.powerups.rebuild(self.powerups.iter().map(|p| (p.pos, p.radius)));
+        for i in self.grids.powerups.query(self.player.pos, self.player.radius) {
+            let p = &mut self.powerups[i];
+            if p.pos.distance(self.player.pos) < p.radius + self.player.radius {
+                p.hits = 0;
+                let (power, offer) = (p.power, p.offer);
+                self.events.push(GameEvent::PowerupTaken(power));
+                self.take_power(power);
+                if offer {
+                    self.picks_left = self.picks_left.saturating_sub(1);
+                }
+            }
+        }
+        self.powerups.retain(|p| p.hits > 0);
+
+        // Update the player
+        Player::update(&controls, self);
+        self.camera.follow(self.player.pos);
+
+        if self.player.life == 0 {
+            self.overlay = Overlay::game_over(self.seed);
+        }
+    }
+
+    /// Everything the player does besides moving goes through here,
+    /// so that a run only depends on the seed and the actions.
+    pub fn action(&mut self, action: Action, mouse: Vector) {
+        if self.rules.controls.aims_with_mouse() {
+            self.player.aim = self.camera.to_world(mouse) - self.player.pos;
+        }
+        match action {
+            Action::Fire => self.fire(),
+            Action::Reroll => self.reroll(),
+            Action::TogglePause => self.toggle_pause(),
+            Action::Restart => self.restart(),
+            Action::NewSeed => self.new_seed(),
+        }
+    }
+
+    /// Fire a volley where the player aims, if the weapon has cooled down.
+    pub fn fire(&mut self) {
+        if self.player.cooldown > 0 {
+            return;
+        }
+        self.player.cooldown = self.player.volley_delay();
+        let shots = self.player.fire();
+        if !shots.is_empty() {
+            self.events.push(GameEvent::ShotFired { laser: self.player.evolved(Evolution::Lasers) });
+        }
+        self.shots.extend(shots);
+    }
+
+    /// The permanent powers go to the player, the timed ones
+    /// to whoever they affect.
+    fn take_power(&mut self, power: Power) {
+        match power.status() {
+            Some(Status::Freeze) => {
+                for e in &mut self.enemies {
+                    if self.camera.sees(e.pos, e.radius) {
+                        e.statuses.apply(Status::Freeze);
+                    }
+                }
+            }
+            Some(status) => self.player.statuses.apply(status),
+            None => {
+                self.player.powerup(power);
+                for e in evolution::unlocked(&self.player.powers, &self.player.evolutions) {
+                    self.player.evolutions.push(e);
+                    self.evolved = Some((e, EVOLVED_FRAMES));
+                    self.events.push(GameEvent::Evolved(e));
+                }
+            }
+        }
+    }
+
+    /// How fast the enemies, the boss and their bullets go, slower in slow motion
+    pub fn time_scale(&self) -> f32 {
+        if self.slowmo > 0 { SLOWMO_SCALE } else { 1.0 }
+    }
+
+    /// Whether the timers of the enemies and the boss count this tick.
+    /// In slow motion they skip ticks, to go as slow as the rest.
+    pub fn clock_ticks(&self) -> bool {
+        let scale = self.time_scale();
+        (self.frame as f32 * scale).floor() != ((self.frame + 1) as f32 * scale).floor()
+    }
+
+    /// The events so far, leaving none behind
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Start the exact same run again.
+    pub fn restart(&mut self) {
+        *self = Game::with_rules(self.seed, self.rules.clone());
+    }
+
+    /// Start a run with a seed drawn from the current one,
+    /// so that replays still reproduce it.
+    pub fn new_seed(&mut self) {
+        let seed = self.rng.gen_range(0, SEED_RANGE);
+        *self = Game::with_rules(seed, self.rules.clone());
+    }
+
+    pub fn toggle_pause(&mut self) {
+
+        // No pause if dead
+        if self.player.life > 0 {
+            self.paused = !self.paused;
+
+            if self.paused {
+                self.overlay = Overlay::pause();
+            } else {
+                self.overlay.visible = false;
+            }
+        }
+    }
+
+    /// Bring the boss of the current wave, and offer
+    /// its powerups once it is defeated.
+    fn spawn_boss(&mut self) {
+        let def = match &self.rules.waves.boss {
+            Some(def) => def,
+            None => return,
+        };
+        let nb = self.wave / def.every - 1;
+        self.boss = Some(Boss::new(def, nb, &self.camera.view()));
+        self.wave_def.powerups = def.powerups.clone();
+        self.wave_def.picks = def.picks;
+    }
+
+    /// Its minions die with it, and the draft comes right away.
+    fn boss_killed(&mut self, boss: &Boss) {
+        self.score += boss.max_life as u32;
+        self.shake += 20;
+        self.events.push(GameEvent::BossKilled);
+        self.particles.extend(boss.explosion(&mut self.rng));
+        for e in &self.enemies {
+            self.particles.extend(e.particles(&mut self.rng, 20.0));
+        }
+        self.enemies = vec![];
+        self.shots.retain(|s| s.owner == Faction::Player);
+
+        self.overlay = Overlay::powerup(self.picks());
+        self.wave_state = WaveState::AnnoncePowerUp(45);
+    }
+
+    /// Whether the current wave should stop spawning enemies
+    fn wave_over(&self) -> bool {
+        match self.wave_def.end {
+            EndCondition::Score(s) => self.score > s,
+            EndCondition::Kills(k) => self.wave_kills >= k,
+            EndCondition::Frames(f) => self.wave_frames >= f,
+        }
+    }
+
+    /// Draw the powerups offered after the wave.
+    fn offer_powerups(&mut self) {
+        let n = self.rules.waves.draft.offers.max(self.wave_def.picks);
+        let offers = draft::draw(&self.wave_def.powerups, n, &mut self.rng);
+        self.picks_left = self.picks();
+        self.lay_out_offers(&offers);
+    }
+
+    /// How many powerups can be taken after this wave, less than
+    /// the script says when there are not as many different ones
+    fn picks(&self) -> usize {
+        let pool = &self.wave_def.powerups;
+        let different = (0..pool.len()).filter(|&i| !pool[..i].contains(&pool[i])).count();
+        self.wave_def.picks.min(different)
+    }
+
+    /// Spend a reroll token to replace the powerups still offered.
+    pub fn reroll(&mut self) {
+        if self.wave_state != WaveState::PowerUp || self.picks_left == 0 || self.player.rerolls == 0 {
+            return;
+        }
+        self.player.rerolls -= 1;
+        let n = self.powerups.iter().filter(|p| p.offer).count();
+        let offers = draft::draw(&self.wave_def.powerups, n, &mut self.rng);
+        self.lay_out_offers(&offers);
+        self.events.push(GameEvent::Rerolled);
+    }
+
+    /// Put the offers on a grid over the view.
+    fn lay_out_offers(&mut self, offers: &[Power]) {
+        let cols = (offers.len() as f32).sqrt().ceil().max(1.0) as usize;
+        let rows = offers.len().div_ceil(cols);
+
+        self.powerups = offers.iter().enumerate().map(|(i, &p)| {
+            let x = (i % cols) as f32 + 0.5;
+            let y = (i / cols) as f32 + 0.5;
+            PowerUp::new_fixed(p, self.camera.pos + SIZE.times(Vector::new(x / cols as f32, y / rows as f32)))
+        }).collect();
+    }
+
+    pub fn spawn_enemy(&mut self) {
+        let wave = &self.wave_def;
+        if self.frame % wave.spawn_every != wave.spawn_offset {return;}
+
+        // Find a position just out of the view
+        let view = self.camera.view();
+        let x = Uniform::new(view.pos.x - 100.0, view.pos.x + view.size.x + 100.0);
+        let y = Uniform::new(view.pos.y - 100.0, view.pos.y + view.size.y + 100.0);
+        let mut pos = view.pos;
+        while self.camera.sees(pos, 50.0) {
+            pos.x = x.sample(&mut self.rng);
+            pos.y = y.sample(&mut self.rng);
+        }
+
+        let level = self.wave_def.levels.sample(&mut self.rng);
+        let kind = self.wave_def.kind(&mut self.rng);
+
+        self.enemies.push(
+            Enemy::new(kind, pos, level)
+        );
+    }
+
+    pub fn spawn_powerup(&mut self) {
+        let b = Bernoulli::from_ratio(1, 30 * POWERUP_DELAY).unwrap();
+        if b.sample(&mut self.rng) {
+            // The draft gives the permanent powers, these are mostly timed
+            let &p = [
+                Power::LifeUp,
+                Power::LifeUp,
+                Power::LifeUp,
+                Power::RapidFire,
+                Power::RapidFire,
+                Power::Berserk,
+                Power::Freeze,
+                Power::Freeze,
+            ].iter().choose(&mut self.rng).unwrap();
+            self.powerups.push(PowerUp::new(p, &self.camera, &mut self.rng));
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,173 @@
+use quicksilver::geom::Vector;
+use super::ARENA;
+
+/// Cell size of the collision grids, about the size of the enemies
+const COLLISION_CELL: f32 = 100.0;
+
+/// A uniform grid of square cells over the arena, to find what is
+/// close to a point without looking at everything. Things outside
+/// of the arena go in the cells of the border.
+///
+/// Items are stored by index, so the grid has to be rebuilt whenever
+/// the collection it indexes changes.
+pub struct SpatialGrid {
+    cell: f32,
+    cols: i32,
+    rows: i32,
+    cells: Vec<Vec<usize>>,
+}
+
+impl SpatialGrid {
+    /// `cell` should be about the size of the queries, and
+    /// `arena` the size of the area to cover
+    pub fn new(cell: f32, arena: Vector) -> Self {
+        debug_assert!(cell > 0.0, "the cells of a grid need a size");
+        let cols = (arena.x / cell).ceil().max(1.0) as i32;
+        let rows = (arena.y / cell).ceil().max(1.0) as i32;
+        SpatialGrid {
+            cell,
+            cols,
+            rows,
+            cells: vec![vec![]; (cols * rows) as usize],
+        }
+    }
+
+    /// A grid of circles, indexed by their position in the iterator
+    pub fn build(cell: f32, arena: Vector, items: impl Iterator<Item = (Vector, f32)>) -> Self {
+        let mut grid = SpatialGrid::new(cell, arena);
+        grid.rebuild(items);
+        grid
+    }
+
+    /// Empty the grid, but keep its memory for the next build
+    pub fn clear(&mut self) {
+        for v in &mut self.cells {
+            v.clear();
+        }
+    }
+
+    /// Replace everything by the circles of the iterator
+    pub fn rebuild(&mut self, items: impl Iterator<Item = (Vector, f32)>) {
+        self.clear();
+        for (i, (pos, radius)) in items.enumerate() {
+            self.insert(i, pos, radius);
+        }
+    }
+
+    /// The range of cells covered by the circle, as (x0, x1, y0, y1)
+    fn span(&self, pos: Vector, radius: f32) -> (i32, i32, i32, i32) {
+        let col = |x: f32| ((x / self.cell).floor() as i32).clamp(0, self.cols - 1);
+        let row = |y: f32| ((y / self.cell).floor() as i32).clamp(0, self.rows - 1);
+        (col(pos.x - radius), col(pos.x + radius), row(pos.y - radius), row(pos.y + radius))
+    }
+
+    /// Add a circle to every cell it overlaps
+    pub fn insert(&mut self, idx: usize, pos: Vector, radius: f32) {
+        let (x0, x1, y0, y1) = self.span(pos, radius);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.cells[(y * self.cols + x) as usize].push(idx);
+            }
+        }
+    }
+
+    /// The indices of everything that may overlap the circle, sorted.
+    ///
+    /// This is only a broad phase: the exact distance still
+    /// has to be checked.
+    pub fn query(&self, pos: Vector, radius: f32) -> Vec<usize> {
+        let (x0, x1, y0, y1) = self.span(pos, radius);
+
+        let mut found = vec![];
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                found.extend_from_slice(&self.cells[(y * self.cols + x) as usize]);
+            }
+        }
+        // Sorted so that the checks happen in the same order as in the collection
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+/// The broad phase of all collision checks, with one grid
+/// for each collection of the game.
+///
+/// Each grid is rebuilt right before the checks that use it,
+/// so the indices match the collections at that time.
+pub struct Grids {
+    pub shots: SpatialGrid,
+    pub enemies: SpatialGrid,
+    pub powerups: SpatialGrid,
+}
+
+impl Grids {
+    pub fn new(arena: Vector) -> Self {
+        Grids {
+            shots: SpatialGrid::new(COLLISION_CELL, arena),
+            enemies: SpatialGrid::new(COLLISION_CELL, arena),
+            powerups: SpatialGrid::new(COLLISION_CELL, arena),
+        }
+    }
+}
+
+impl Default for Grids {
+    fn default() -> Self {
+        Grids::new(ARENA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+    use rand_distr::Uniform;
+    use rand_xorshift::XorShiftRng;
+
+    /// Circles all over the arena, some of them big or well outside of it
+    fn circles(rng: &mut XorShiftRng, n: usize) -> Vec<(Vector, f32)> {
+        let x = Uniform::new(-300.0, ARENA.x + 300.0);
+        let y = Uniform::new(-300.0, ARENA.y + 300.0);
+        let radius = Uniform::new(0.0, 250.0);
+        (0..n).map(|_| (Vector::new(x.sample(rng), y.sample(rng)), radius.sample(rng))).collect()
+    }
+
+    fn overlap(a: (Vector, f32), b: (Vector, f32)) -> bool {
+        (a.0 - b.0).len2() < (a.1 + b.1).powi(2)
+    }
+
+    #[test]
+    fn same_as_brute_force() {
+        let mut rng = XorShiftRng::seed_from_u64(8);
+        let mut grid = SpatialGrid::new(COLLISION_CELL, ARENA);
+        for _ in 0..20 {
+            let items = circles(&mut rng, 200);
+            grid.rebuild(items.iter().copied());
+
+            for query in circles(&mut rng, 200) {
+                let found = grid.query(query.0, query.1);
+                let mut sorted = found.clone();
+                sorted.sort_unstable();
+                sorted.dedup();
+                assert_eq!(found, sorted, "query results are sorted and unique");
+
+                let close: Vec<usize> = found.into_iter().filter(|&i| overlap(items[i], query)).collect();
+                let brute: Vec<usize> = (0..items.len()).filter(|&i| overlap(items[i], query)).collect();
+                assert_eq!(close, brute, "{:?}", query);
+            }
+        }
+    }
+
+    #[test]
+    fn outside_goes_to_the_border() {
+        let items = vec![
+            (Vector::new(-1000.0, -1000.0), 1.0),
+            (ARENA + Vector::new(1000.0, 1000.0), 1.0),
+        ];
+        let grid = SpatialGrid::build(COLLISION_CELL, ARENA, items.into_iter());
+        assert_eq!(grid.query(Vector::ZERO, 1.0), vec![0]);
+        assert_eq!(grid.query(ARENA, 1.0), vec![1]);
+        assert_eq!(grid.query(ARENA / 2.0, 10.0), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,220 @@
+use quicksilver::{
+    log,
+    saving::{self, Location},
+};
+use serde::{Serialize, Deserialize};
+
+use super::{Game, Power};
+
+/// How many runs are kept in the table
+pub const HIGHSCORES_SIZE: usize = 10;
+
+const APP_NAME: &str = "chaos";
+const PROFILE: &str = "highscores";
+
+/// One finished run
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub score: u32,
+    pub wave: u32,
+    pub shots: i32,
+    pub pierce: i32,
+    pub damage: i32,
+    /// How many extra lives were taken
+    pub lives: usize,
+    pub seed: u64,
+    /// Seconds since the unix epoch
+    pub date: u64,
+}
+
+impl ScoreEntry {
+    /// Summarize the run of `game`, which should have just ended.
+    pub fn new(game: &Game, date: u64) -> Self {
+        let player = &game.player;
+        ScoreEntry {
+            score: game.score,
+            wave: game.wave,
+            shots: player.shots,
+            pierce: player.pierce,
+            damage: player.damage,
+            lives: player.powers.iter().filter(|p| matches!(p, Power::LifeUp)).count(),
+            seed: game.seed,
+            date,
+        }
+    }
+
+    /// The date of the run as YYYY-MM-DD
+    pub fn day(&self) -> String {
+        // Howard Hinnant's civil_from_days
+        let z = (self.date / 86400) as i64 + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// Where the table is kept between runs.
+pub trait ScoreStore {
+    fn load(&self) -> Vec<ScoreEntry>;
+    fn save(&mut self, entries: &[ScoreEntry]);
+}
+
+/// Saves to a file on desktop and to the localStorage on the web.
+pub struct SavedStore;
+
+impl ScoreStore for SavedStore {
+    fn load(&self) -> Vec<ScoreEntry> {
+        saving::load(Location::Data, APP_NAME, PROFILE).unwrap_or_default()
+    }
+
+    fn save(&mut self, entries: &[ScoreEntry]) {
+        if let Err(e) = saving::save(Location::Data, APP_NAME, PROFILE, &entries) {
+            log::warn!("Could not save the high scores: {}", e);
+        }
+    }
+}
+
+/// Keeps nothing once dropped, for tests and headless runs.
+#[derive(Default)]
+pub struct MemoryStore {
+    pub entries: Vec<ScoreEntry>,
+}
+
+impl ScoreStore for MemoryStore {
+    fn load(&self) -> Vec<ScoreEntry> {
+        self.entries.clone()
+    }
+
+    fn save(&mut self, entries: &[ScoreEntry]) {
+        self.entries = entries.to_vec();
+    }
+}
+
+/// The best runs, best first.
+pub struct HighScores {
+    store: Box<dyn ScoreStore>,
+    entries: Vec<ScoreEntry>,
+    /// Rank of the last inserted run, if it made it in the table
+    pub last_rank: Option<usize>,
+}
+
+impl HighScores {
+    pub fn new(store: Box<dyn ScoreStore>) -> Self {
+        let mut entries = store.load();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.score));
+        entries.truncate(HIGHSCORES_SIZE);
+        HighScores {
+            store,
+            entries,
+            last_rank: None,
+        }
+    }
+
+    pub fn entries(&self) -> &[ScoreEntry] {
+        &self.entries
+    }
+
+    /// Add a run to the table and save it. Return its rank,
+    /// or None if it is not good enough to be kept.
+    pub fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        // After the runs with the same score, as they were there first
+        let rank = self.entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+
+        self.last_rank = if rank < HIGHSCORES_SIZE {
+            self.entries.insert(rank, entry);
+            self.entries.truncate(HIGHSCORES_SIZE);
+            self.store.save(&self.entries);
+            Some(rank)
+        } else {
+            None
+        };
+        self.last_rank
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// A store the test can still look into
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<MemoryStore>>);
+
+    impl ScoreStore for Shared {
+        fn load(&self) -> Vec<ScoreEntry> {
+            self.0.borrow().load()
+        }
+
+        fn save(&mut self, entries: &[ScoreEntry]) {
+            self.0.borrow_mut().save(entries)
+        }
+    }
+
+    fn run(score: u32, seed: u64) -> ScoreEntry {
+        ScoreEntry { score, wave: 1, shots: 1, pierce: 1, damage: 1, lives: 0, seed, date: 0 }
+    }
+
+    fn scores(table: &HighScores) -> Vec<(u32, u64)> {
+        table.entries().iter().map(|e| (e.score, e.seed)).collect()
+    }
+
+    #[test]
+    fn ranking_and_ties() {
+        let mut table = HighScores::new(Box::new(MemoryStore::default()));
+        assert_eq!(table.insert(run(10, 0)), Some(0));
+        assert_eq!(table.insert(run(30, 1)), Some(0));
+        assert_eq!(table.insert(run(20, 2)), Some(1));
+        // After the one that was there first
+        assert_eq!(table.insert(run(20, 3)), Some(2));
+        assert_eq!(table.last_rank, Some(2));
+        assert_eq!(scores(&table), vec![(30, 1), (20, 2), (20, 3), (10, 0)]);
+    }
+
+    #[test]
+    fn only_the_best_are_kept() {
+        let store = Shared::default();
+        let mut table = HighScores::new(Box::new(store.clone()));
+        for i in 0..HIGHSCORES_SIZE as u32 {
+            assert_eq!(table.insert(run(10 * (i + 1), i as u64)), Some(0));
+        }
+
+        // Ties with the last one, so it comes after it
+        assert_eq!(table.insert(run(10, 100)), None);
+        assert_eq!(table.last_rank, None);
+        assert_eq!(store.0.borrow().entries.len(), HIGHSCORES_SIZE);
+        assert!(store.0.borrow().entries.iter().all(|e| e.seed != 100));
+
+        assert_eq!(table.insert(run(15, 101)), Some(HIGHSCORES_SIZE - 1));
+        assert_eq!(table.entries().len(), HIGHSCORES_SIZE);
+        assert_eq!(table.entries().last().unwrap().seed, 101);
+        assert_eq!(store.0.borrow().entries, table.entries());
+
+        // The next session reads the same table
+        let again = HighScores::new(Box::new(store));
+        assert_eq!(scores(&again), scores(&table));
+    }
+
+    #[test]
+    fn days() {
+        let day = |date| ScoreEntry { date, ..run(0, 0) }.day();
+        assert_eq!(day(0), "1970-01-01");
+        // 2024 is a leap year, 2100 is not
+        assert_eq!(day(1_709_164_800), "2024-02-29");
+        assert_eq!(day(1_709_164_800 + 86_399), "2024-02-29");
+        assert_eq!(day(1_709_251_200), "2024-03-01");
+        assert_eq!(day(1_704_067_199), "2023-12-31");
+        assert_eq!(day(1_704_067_200), "2024-01-01");
+        assert_eq!(day(4_107_456_000), "2100-02-28");
+        assert_eq!(day(4_107_542_400), "2100-03-01");
+    }
+}
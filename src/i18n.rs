@@ -0,0 +1,378 @@
+//! The text shown to the player, in every language the game ships.
+//!
+//! Each locale is a flat JSON table from message keys to strings, under
+//! `static/locales`, and is compiled in. The simulation only deals with
+//! `Message`s, an ID and its arguments, and the rendering turns them into
+//! text in the chosen language. A key missing from a locale falls back to
+//! English.
+//!
+//! The pixel font has no accented letters, so the locales are written
+//! without them.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// The shipped locales, English first as it is the fallback
+pub const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../static/locales/en.json")),
+    ("fr", include_str!("../static/locales/fr.json")),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Msg {
+    /// The name of the language, in that language
+    LanguageName,
+    GameTitle,
+    GameOver,
+    Paused,
+    PickPowerups,
+    /// {0}: the wave number
+    Wave,
+    /// {0}: the wave number
+    BossWave,
+    /// {0}: the seed
+    Seed,
+    Score,
+    NoRuns,
+    /// {0} to {8}: rank, score, wave, shots, pierce, damage, lives, seed, day
+    ScoreRow,
+    /// One instruction per line. {0}: how to move and fire,
+    /// {1}: when the bosses come, {2}: the pause key
+    HowToPlayText,
+    HowToMoveMouse,
+    /// {0}: the keys to move
+    HowToMoveKeysAndMouse,
+    /// {0}: the keys to move, {1}: the keys to aim, {2}: the fire key
+    HowToMoveKeys,
+    /// {0}: the waves between two bosses
+    BossEvery,
+    Play,
+    Options,
+    HighScores,
+    HowToPlay,
+    Resume,
+    Retry,
+    NewSeed,
+    TitleScreen,
+    Back,
+    On,
+    Off,
+    /// {0}: on or off
+    Boids,
+    /// {0}: on or off
+    BigArena,
+    /// {0}: on or off
+    Music,
+    /// {0}: on or off
+    Sounds,
+    /// {0}: the name of the language
+    Language,
+    /// {0}: the control scheme
+    Controls,
+    SchemeMouse,
+    SchemeKeysAndMouse,
+    SchemeKeys,
+    Keys,
+    /// {0}: what the key does, {1}: the key
+    Binding,
+    PressKey,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    AimUp,
+    AimDown,
+    AimLeft,
+    AimRight,
+    Fire,
+    Pause,
+    Reroll,
+    /// {0}: the key, {1}: the reroll tokens left
+    Rerolls,
+    Dash,
+    RapidFire,
+    Berserk,
+    Freeze,
+    Burn,
+    /// {0}: a timed power, {1}: the seconds left
+    StatusLeft,
+    /// {0}: the name of the evolution
+    Evolved,
+    Lasers,
+    Ring,
+    Storm,
+    Reflector,
+    Stats,
+    StatDps,
+    StatShots,
+    StatVolleys,
+    StatDamage,
+    StatPierce,
+    /// {0}: the frames left, {1}: the frames after each hit
+    StatInvincible,
+}
+
+impl Msg {
+    pub const ALL: [Msg; 69] = [
+        Msg::LanguageName,
+        Msg::GameTitle,
+        Msg::GameOver,
+        Msg::Paused,
+        Msg::PickPowerups,
+        Msg::Wave,
+        Msg::BossWave,
+        Msg::Seed,
+        Msg::Score,
+        Msg::NoRuns,
+        Msg::ScoreRow,
+        Msg::HowToPlayText,
+        Msg::HowToMoveMouse,
+        Msg::HowToMoveKeysAndMouse,
+        Msg::HowToMoveKeys,
+        Msg::BossEvery,
+        Msg::Play,
+        Msg::Options,
+        Msg::HighScores,
+        Msg::HowToPlay,
+        Msg::Resume,
+        Msg::Retry,
+        Msg::NewSeed,
+        Msg::TitleScreen,
+        Msg::Back,
+        Msg::On,
+        Msg::Off,
+        Msg::Boids,
+        Msg::BigArena,
+        Msg::Music,
+        Msg::Sounds,
+        Msg::Language,
+        Msg::Controls,
+        Msg::SchemeMouse,
+        Msg::SchemeKeysAndMouse,
+        Msg::SchemeKeys,
+        Msg::Keys,
+        Msg::Binding,
+        Msg::PressKey,
+        Msg::MoveUp,
+        Msg::MoveDown,
+        Msg::MoveLeft,
+        Msg::MoveRight,
+        Msg::AimUp,
+        Msg::AimDown,
+        Msg::AimLeft,
+        Msg::AimRight,
+        Msg::Fire,
+        Msg::Pause,
+        Msg::Reroll,
+        Msg::Rerolls,
+        Msg::Dash,
+        Msg::RapidFire,
+        Msg::Berserk,
+        Msg::Freeze,
+        Msg::Burn,
+        Msg::StatusLeft,
+        Msg::Evolved,
+        Msg::Lasers,
+        Msg::Ring,
+        Msg::Storm,
+        Msg::Reflector,
+        Msg::Stats,
+        Msg::StatDps,
+        Msg::StatShots,
+        Msg::StatVolleys,
+        Msg::StatDamage,
+        Msg::StatPierce,
+        Msg::StatInvincible,
+    ];
+
+    /// Its key in the locale files
+    pub fn key(self) -> &'static str {
+        match self {
+            Msg::LanguageName => "language_name",
+            Msg::GameTitle => "game_title",
+            Msg::GameOver => "game_over",
+            Msg::Paused => "paused",
+            Msg::PickPowerups => "pick_powerups",
+            Msg::Wave => "wave",
+            Msg::BossWave => "boss_wave",
+            Msg::Seed => "seed",
+            Msg::Score => "score",
+            Msg::NoRuns => "no_runs",
+            Msg::ScoreRow => "score_row",
+            Msg::HowToPlayText => "how_to_play_text",
+            Msg::HowToMoveMouse => "how_to_move_mouse",
+            Msg::HowToMoveKeysAndMouse => "how_to_move_keys_and_mouse",
+            Msg::HowToMoveKeys => "how_to_move_keys",
+            Msg::BossEvery => "boss_every",
+            Msg::Play => "play",
+            Msg::Options => "options",
+            Msg::HighScores => "high_scores",
+            Msg::HowToPlay => "how_to_play",
+            Msg::Resume => "resume",
+            Msg::Retry => "retry",
+            Msg::NewSeed => "new_seed",
+            Msg::TitleScreen => "title_screen",
+            Msg::Back => "back",
+            Msg::On => "on",
+            Msg::Off => "off",
+            Msg::Boids => "boids",
+            Msg::BigArena => "big_arena",
+            Msg::Music => "music",
+            Msg::Sounds => "sounds",
+            Msg::Language => "language",
+            Msg::Controls => "controls",
+            Msg::SchemeMouse => "scheme_mouse",
+            Msg::SchemeKeysAndMouse => "scheme_keys_and_mouse",
+            Msg::SchemeKeys => "scheme_keys",
+            Msg::Keys => "keys",
+            Msg::Binding => "binding",
+            Msg::PressKey => "press_key",
+            Msg::MoveUp => "move_up",
+            Msg::MoveDown => "move_down",
+            Msg::MoveLeft => "move_left",
+            Msg::MoveRight => "move_right",
+            Msg::AimUp => "aim_up",
+            Msg::AimDown => "aim_down",
+            Msg::AimLeft => "aim_left",
+            Msg::AimRight => "aim_right",
+            Msg::Fire => "fire",
+            Msg::Pause => "pause",
+            Msg::Reroll => "reroll",
+            Msg::Rerolls => "rerolls",
+            Msg::Dash => "dash",
+            Msg::RapidFire => "rapid_fire",
+            Msg::Berserk => "berserk",
+            Msg::Freeze => "freeze",
+            Msg::Burn => "burn",
+            Msg::StatusLeft => "status_left",
+            Msg::Evolved => "evolved",
+            Msg::Lasers => "lasers",
+            Msg::Ring => "ring",
+            Msg::Storm => "storm",
+            Msg::Reflector => "reflector",
+            Msg::Stats => "stats",
+            Msg::StatDps => "stat_dps",
+            Msg::StatShots => "stat_shots",
+            Msg::StatVolleys => "stat_volleys",
+            Msg::StatDamage => "stat_damage",
+            Msg::StatPierce => "stat_pierce",
+            Msg::StatInvincible => "stat_invincible",
+        }
+    }
+}
+
+/// A message with its arguments, translated when it is drawn
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    pub msg: Msg,
+    pub args: Vec<String>,
+}
+
+impl Message {
+    pub fn new(msg: Msg) -> Self {
+        Message { msg, args: vec![] }
+    }
+
+    /// Fills the next `{n}` of the text
+    pub fn arg(mut self, arg: impl Display) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+}
+
+impl From<Msg> for Message {
+    fn from(msg: Msg) -> Self {
+        Message::new(msg)
+    }
+}
+
+fn parse(locale: &str) -> HashMap<String, String> {
+    serde_json::from_str(locale).unwrap_or_default()
+}
+
+/// The strings of one language.
+pub struct Strings {
+    /// Its code, like "en"
+    pub lang: &'static str,
+    table: HashMap<String, String>,
+    english: HashMap<String, String>,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Strings::new("en")
+    }
+}
+
+impl Strings {
+    /// The locale for a language code like "fr" or "fr-FR",
+    /// English when it is not shipped.
+    pub fn new(lang: &str) -> Self {
+        let lang = lang.split(['-', '_', '.']).next().unwrap_or("").to_lowercase();
+        let &(code, locale) = LOCALES.iter().find(|l| l.0 == lang).unwrap_or(&LOCALES[0]);
+        Strings {
+            lang: code,
+            table: parse(locale),
+            english: parse(LOCALES[0].1),
+        }
+    }
+
+    /// The locale after this one, to cycle through them
+    pub fn next(&self) -> Self {
+        let i = LOCALES.iter().position(|l| l.0 == self.lang).unwrap_or(0);
+        Strings::new(LOCALES[(i + 1) % LOCALES.len()].0)
+    }
+
+    /// The raw text of a message, `{n}` placeholders included
+    pub fn get(&self, msg: Msg) -> &str {
+        let key = msg.key();
+        self.table.get(key)
+            .or_else(|| self.english.get(key))
+            .map_or(key, |s| s.as_str())
+    }
+
+    /// The text of a message, with its arguments
+    pub fn text(&self, message: &Message) -> String {
+        message.args.iter().enumerate().fold(self.get(message.msg).to_string(), |text, (i, arg)| {
+            text.replace(&format!("{{{}}}", i), arg)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `{n}` placeholders of a text
+    fn placeholders(text: &str) -> Vec<usize> {
+        (0..10).filter(|i| text.contains(&format!("{{{}}}", i))).collect()
+    }
+
+    #[test]
+    fn every_key_in_every_locale() {
+        let english = parse(LOCALES[0].1);
+        for &(lang, locale) in LOCALES {
+            let table: HashMap<String, String> = serde_json::from_str(locale)
+                .unwrap_or_else(|e| panic!("{}.json is invalid: {}", lang, e));
+            for msg in Msg::ALL.iter() {
+                let text = table.get(msg.key())
+                    .unwrap_or_else(|| panic!("{} is missing from {}.json", msg.key(), lang));
+                assert_eq!(
+                    placeholders(text), placeholders(&english[msg.key()]),
+                    "{} does not take the same arguments in {}.json", msg.key(), lang);
+                assert!(text.is_ascii(), "{} in {}.json has letters the font does not have", msg.key(), lang);
+            }
+            for key in table.keys() {
+                assert!(Msg::ALL.iter().any(|m| m.key() == key), "{}.json has an unknown key {}", lang, key);
+            }
+        }
+    }
+
+    #[test]
+    fn fallback_to_english() {
+        assert_eq!(Strings::new("xx").lang, "en");
+        assert_eq!(Strings::new("fr-FR").lang, "fr");
+        let strings = Strings::new("en");
+        assert_eq!(strings.text(&Message::new(Msg::Wave).arg(3)), "Wave 3");
+    }
+}
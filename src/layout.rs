@@ -0,0 +1,190 @@
+//! Text laid out with the real metrics of the font.
+//!
+//! Strings are measured by laying out their glyphs without drawing them,
+//! so centring, right alignment and wrapping match what ends up on screen
+//! whatever the font and its size.
+
+use quicksilver::{
+    geom::{Rectangle, Vector},
+    graphics::{Color, FontRenderer, VectorFont},
+    Graphics, Result,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FontSize {
+    Small,
+    Medium,
+    Big,
+}
+
+impl FontSize {
+    const ALL: [FontSize; 3] = [FontSize::Small, FontSize::Medium, FontSize::Big];
+
+    fn pixels(self) -> f32 {
+        match self {
+            FontSize::Small => 36.0,
+            FontSize::Medium => 54.0,
+            FontSize::Big => 72.0,
+        }
+    }
+}
+
+/// Where the text is relative to the x of its position
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Style {
+    pub size: FontSize,
+    pub color: Color,
+    pub align: Align,
+    /// Lines longer than this are wrapped between words
+    pub max_width: Option<f32>,
+}
+
+impl Style {
+    pub fn new(size: FontSize, color: Color) -> Self {
+        Style {
+            size,
+            color,
+            align: Align::Left,
+            max_width: None,
+        }
+    }
+
+    pub fn align(self, align: Align) -> Self {
+        Style { align, ..self }
+    }
+
+    pub fn wrap(self, max_width: f32) -> Self {
+        Style { max_width: Some(max_width), ..self }
+    }
+}
+
+/// One renderer per size of the same font.
+///
+/// Panics when a glyph can not be rendered, like drawing with a
+/// `FontRenderer` and unwrapping would.
+pub struct Fonts {
+    renderers: Vec<FontRenderer>,
+}
+
+impl Fonts {
+    pub fn new(ttf: &VectorFont, gfx: &Graphics) -> Result<Self> {
+        let renderers = FontSize::ALL
+            .iter()
+            .map(|s| ttf.to_renderer(gfx, s.pixels()))
+            .collect::<Result<_>>()?;
+        Ok(Fonts { renderers })
+    }
+
+    fn renderer(&mut self, size: FontSize) -> &mut FontRenderer {
+        &mut self.renderers[size as usize]
+    }
+
+    /// Lays out the glyphs without drawing them, returns the bounding
+    /// box of the glyphs relative to the baseline of the first line
+    fn measure(&mut self, gfx: &mut Graphics, size: FontSize, text: &str) -> Rectangle {
+        let mut top = 0.0f32;
+        let extents = self
+            .renderer(size)
+            .layout_glyphs(gfx, text, None, |_, glyph| top = top.min(glyph.position.y))
+            .unwrap();
+        Rectangle::new(Vector::new(0.0, top), Vector::new(extents.x, extents.y - top))
+    }
+
+    /// From the left of the first glyph to the right of the last one
+    pub fn width(&mut self, gfx: &mut Graphics, size: FontSize, text: &str) -> f32 {
+        text.lines().map(|l| self.measure(gfx, size, l).size.x).fold(0.0, f32::max)
+    }
+
+    /// How far the pen moves for `text`, trailing spaces included
+    fn advance(&mut self, gfx: &mut Graphics, size: FontSize, text: &str) -> f32 {
+        self.width(gfx, size, &format!("{}|", text)) - self.width(gfx, size, "|")
+    }
+
+    /// From a baseline to the next
+    pub fn line_height(&mut self, gfx: &mut Graphics, size: FontSize) -> f32 {
+        self.renderer(size).layout_glyphs(gfx, "", None, |_, _| {}).unwrap().y
+    }
+
+    /// How high the capitals go above the baseline
+    pub fn cap_height(&mut self, gfx: &mut Graphics, size: FontSize) -> f32 {
+        -self.measure(gfx, size, "H").pos.y
+    }
+
+    /// Splits the text in the lines it is drawn on
+    pub fn lines(&mut self, gfx: &mut Graphics, size: FontSize, text: &str, max_width: Option<f32>) -> Vec<String> {
+        let mut lines = vec![];
+        for paragraph in text.split('\n') {
+            let max_width = match max_width {
+                Some(w) => w,
+                None => {
+                    lines.push(paragraph.to_string());
+                    continue;
+                }
+            };
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let longer = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+                if !line.is_empty() && self.width(gfx, size, &longer) > max_width {
+                    lines.push(std::mem::replace(&mut line, word.to_string()));
+                } else {
+                    line = longer;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Draw `text` with the baseline of its first line at `pos.y`.
+    /// Returns the size of the whole block.
+    pub fn draw(&mut self, gfx: &mut Graphics, text: &str, pos: Vector, style: Style) -> Vector {
+        let lines = self.lines(gfx, style.size, text, style.max_width);
+        let line_height = self.line_height(gfx, style.size);
+
+        let mut width = 0.0f32;
+        for (i, line) in lines.iter().enumerate() {
+            let w = self.width(gfx, style.size, line);
+            width = width.max(w);
+            let x = aligned(pos.x, w, style.align);
+            let baseline = pos.y + i as f32 * line_height;
+            self.renderer(style.size).draw(gfx, line, style.color, Vector::new(x, baseline)).unwrap();
+        }
+        Vector::new(width, lines.len() as f32 * line_height)
+    }
+
+    /// Draw a single line centred in `rect`, the capitals being
+    /// vertically in the middle
+    pub fn draw_in(&mut self, gfx: &mut Graphics, text: &str, rect: &Rectangle, style: Style) {
+        let cap = self.cap_height(gfx, style.size);
+        let pos = rect.pos + rect.size / 2.0 + Vector::new(0.0, cap / 2.0);
+        self.draw(gfx, text, pos, style.align(Align::Center));
+    }
+
+    /// Draw a single line made of parts of different colours, the
+    /// colour of the style being unused
+    pub fn draw_spans(&mut self, gfx: &mut Graphics, spans: &[(&str, Color)], pos: Vector, style: Style) {
+        let whole: String = spans.iter().map(|s| s.0).collect();
+        let w = self.width(gfx, style.size, &whole);
+        let mut x = aligned(pos.x, w, style.align);
+        for &(text, color) in spans {
+            self.renderer(style.size).draw(gfx, text, color, Vector::new(x, pos.y)).unwrap();
+            x += self.advance(gfx, style.size, text);
+        }
+    }
+}
+
+/// The left of a line `width` wide aligned on `x`
+fn aligned(x: f32, width: f32, align: Align) -> f32 {
+    match align {
+        Align::Left => x,
+        Align::Center => x - width / 2.0,
+        Align::Right => x - width,
+    }
+}
@@ -0,0 +1,77 @@
+//! The simulation core of Chaos.
+//!
+//! Everything in this crate runs without a window or a graphics backend:
+//! the game state only uses quicksilver's plain `Vector` and `Color` types,
+//! and the rendering layer lives in the binary and only reads that state.
+
+use quicksilver::geom::Vector;
+use rand_xorshift::XorShiftRng;
+
+pub mod colors;
+pub mod particles;
+pub mod player;
+pub mod shot;
+pub mod enemy;
+pub mod background;
+pub mod overlay;
+pub mod powerup;
+pub mod game;
+pub mod replay;
+pub mod platform;
+pub mod highscores;
+pub mod waves;
+pub mod rules;
+pub mod boss;
+pub mod grid;
+pub mod flock;
+pub mod sfx;
+pub mod events;
+pub mod audio;
+pub mod music;
+pub mod menu;
+pub mod i18n;
+pub mod camera;
+pub mod controls;
+pub mod settings;
+pub mod draft;
+pub mod powers;
+pub mod status;
+pub mod evolution;
+
+use colors::*;
+use particles::*;
+use player::*;
+use shot::*;
+use powerup::*;
+use enemy::{Enemy, EnemyKind};
+use boss::BossDef;
+use waves::WaveScript;
+use grid::SpatialGrid;
+use flock::Flocking;
+use camera::{BigArena, Camera};
+use controls::{ControlScheme, Controls};
+use events::GameEvent;
+use draft::Draft;
+use status::{Status, Statuses};
+use evolution::Evolution;
+
+pub use game::{Game, WaveState};
+pub use replay::{Action, Replay, Recorder};
+pub use rules::Rules;
+
+
+/// The virtual resolution: everything is drawn as if the window was this
+/// size, and scaled to the real one with letterboxing.
+pub const SIZE: Vector = Vector { x: 1300.0, y: 800.0 };
+
+/// Where the simulation happens, unless the rules ask for a bigger arena.
+/// It does not depend on the window, only the view of it does.
+pub const ARENA: Vector = Vector { x: 1300.0, y: 800.0 };
+
+/// Return whether a vector is in an arena of this size, with a 50 pixels margin
+pub fn in_arena(pos: &Vector, arena: Vector) -> bool {
+    pos.x > -50.0
+    && pos.y > -50.0
+    && pos.x < arena.x + 50.0
+    && pos.y < arena.y + 50.0
+}
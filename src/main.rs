@@ -1,390 +1,108 @@
 use quicksilver::{
-    geom::{Vector, Transform},
-    graphics::{Color, VectorFont, FontRenderer, },
+    geom::Vector,
+    graphics::{VectorFont, ResizeHandler},
     input::{Event, Key},
+    load_file, log,
     run, Graphics, Input, Result, Settings, Window, Timer,
 };
 
-use rand::prelude::*;
-use rand_distr::*;
-use rand_xorshift::XorShiftRng;
-use std::mem::swap;
-
-mod colors;
-mod particles;
-mod player;
-mod shot;
-mod enemy;
-mod background;
-mod overlay;
-mod powerup;
-
-use colors::*;
-use particles::*;
-use player::*;
-use shot::*;
-use enemy::*;
-use background::*;
-use overlay::*;
-use powerup::*;
-
-
-const SIZE: Vector = Vector { x: 1300.0, y: 800.0 };
-const POWERUP_DELAY: u32 = 20;
-
-// const BOOM_BYTES: &'static [u8] = include_bytes!("sound/boom.wav");
-// const LASER_BYTES: &'static [u8] = include_bytes!("sound/laser.wav");
-// const POWERUP_BYTES: &'static [u8] = include_bytes!("sound/powerup.wav");
-
-// const BOOM: usize = 0;
-// const LASER: usize = 1;
-// const POWERUP: usize = 2;
-
-/// Return whether a vector is in the screen, with a 50 pixels margin
-fn in_screen(pos: &Vector) -> bool {
-    pos.x > -50.0
-    && pos.y > -50.0
-    && pos.x < SIZE.x + 50.0
-    && pos.y < SIZE.y + 50.0
+use chaos::{Game, SIZE, Action, Replay, Recorder, Rules};
+use chaos::waves::WaveScript;
+use chaos::flock::Flocking;
+use chaos::camera::BigArena;
+use chaos::game::SEED_RANGE;
+use chaos::platform;
+use chaos::highscores::{HighScores, SavedStore, ScoreEntry};
+use chaos::audio::{Mixer, Category};
+use chaos::menu::{Screens, Screen, MenuItem, MenuInput, MenuOptions};
+use chaos::music::MusicState;
+use chaos::sfx::SAMPLE_RATE;
+use chaos::i18n::Strings;
+use chaos::controls::{Bindings, Command};
+use chaos::settings::Settings as SavedSettings;
+
+mod layout;
+mod render;
+
+use layout::Fonts;
+use render::Renderer;
+
+/// The game updates 30 times per second, and the sound follows it
+const SAMPLES_PER_TICK: usize = SAMPLE_RATE as usize / 30;
+
+/// What can be set from the command line
+#[derive(Default)]
+struct Options {
+    /// The seed of the first run
+    seed: Option<u64>,
+    /// Enemies move like boids
+    boids: bool,
+    /// Play in a world larger than the screen
+    arena: bool,
+    /// Save the inputs of each run to this file when the player dies
+    record: Option<String>,
+    /// Play this file instead of listening to the player
+    replay: Option<Replay>,
+    /// The code of the language of the text
+    lang: Option<String>,
 }
 
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum WaveState {
-    Ongoing,
-    WaitToEnd,
-    AnnoncePowerUp(u32),
-    PowerUp,
-    AnnounceWave(u32),
-}
-
-pub struct Game {
-    // Utilities
-    bg_color: Color,
-    rng: XorShiftRng,
-    font: FontRenderer,
-    // Entities
-    particles: Vec<Particle>,
-    player: Player,
-    enemies: Vec<Enemy>,
-    shots: Vec<Shot>,
-    powerups: Vec<PowerUp>,
-    // General
-    frame: u32,
-    paused: bool,
-    score: u32,
-    shake: i32,
-    wave: u32,
-    wave_state: WaveState,
-    bg: Background,
-    overlay: Overlay,
-    // sounds: Vec<Sound>
-}
-
-impl Game {
-    fn new(font: FontRenderer) -> Self {
-        let mut rng = XorShiftRng::from_seed([42; 16]);
-        let mut g = Game { 
-            bg: Background::new(&mut rng),
-            bg_color: Color::from_hex("#020812"),
-            rng: rng,
-            font: font,
-
-            particles: vec![],
-            player: Player::new(),
-            shots: vec![],
-            enemies: vec![],
-            powerups: vec![],
-
-            paused: false,
-            wave: 0,
-            wave_state: WaveState::PowerUp,
-            score: 0,
-            frame: 0,
-            shake: 0,
-            overlay: Overlay::pause(),
-            // mixer: SoundMixer::new(),
-        };
-        g.overlay.visible = false;
-
-        g
-    }
-
-    /// Draw the entire game on the gfx. `prop` is the
-    /// proportion of time between the last update and the next
-    /// prop is in the range 0..1
-    fn draw(&mut self, gfx: &mut Graphics, prop: f32, _render_skip: usize) {
-        if self.paused || self.player.life == 0 {
-            // Otherwise things jitter when paused.
-            // prop = 0.0;
-        }
-        gfx.clear(self.bg_color);
-        self.bg.draw(gfx, self.score);
-
-        // Shakes
-
-        if self.shake > 0 {
-            self.shake = (self.shake - 1).min(20);
-            let angle = Uniform::new(0.0, 360.0);
-            let unif = Uniform::new(5.0, 15.0);
-            gfx.set_transform(Transform::translate(
-                Vector::from_angle(angle.sample(&mut self.rng)) * unif.sample(&mut self.rng)
-            ));
-        } else {
-            gfx.set_transform(Transform::IDENTITY);
-        }
-
-        // Particles and poweups
-
-        for e in &self.enemies {
-            e.draw(gfx, prop);
-        }
-
-        for p in &self.particles {
-            p.draw(gfx, prop);
-        }
-
-        for p in &self.powerups {
-            p.draw(gfx, prop);
-        }
-
-        // Text
-
-        let pos = self.font.draw(
-            gfx, 
-            &"Score: ",
-            Color::WHITE, 
-            Vector::new(10.0, 50.0)
-        ).unwrap();
-        self.font.draw(
-            gfx, 
-            &format!("{}", self.score), 
-            Color::YELLOW, 
-            Vector::new(pos.x + 36.0, 50.0)
-        ).unwrap();
-
-        let life = "<3".repeat(self.player.life);
-        self.font.draw(
-            gfx, &life, Color::RED, 
-            Vector::new(SIZE.x - self.player.life as f32 * 60.0 - 10.0, 50.0)).unwrap();
-
-        self.overlay.draw(gfx, &mut self.font);
-    }
-
-    fn collect_particles(&mut self) {
-        // Update and remove dead particles
-        // We do it first so particles added this frame can
-        // be drawn where they spawn at least once
-        self.particles = self.particles
-            .iter_mut()
-            .filter_map(|p| if p.update() { Some(p.clone()) } else { None } )
-            .collect();
-
-        self.particles.extend(self.overlay.particles());
-        for s in &self.shots {
-            self.particles.extend(s.particles(&mut self.rng));
-        }
-        self.particles.extend(self.player.particles(&mut self.rng));
-        let density = (200.0 / (50 + self.enemies.len()) as f32).max(0.3);
-        for e in &self.enemies {
-            self.particles.extend(e.particles(&mut self.rng, density));
-        }
-        for p in &self.powerups {
-            self.particles.extend(p.particles(&mut self.rng));
-        }
-    }
-
-    fn update(&mut self, mouse: Vector) {
-
-        self.bg.update(self.score);
-        self.collect_particles();
-
-        if self.player.life == 0 { return; }
-        if self.paused { return; }
-
-        self.frame += 1;
-
-        self.wave_state = if self.wave_state == WaveState::Ongoing && self.score > (4 as u32).pow(self.wave + 1) {
-            WaveState::WaitToEnd
-        } else if self.wave_state == WaveState::WaitToEnd && self.enemies.len() == 0 {
-            self.overlay = Overlay::powerup();
-            WaveState::AnnoncePowerUp(45)
-        } else if let WaveState::AnnoncePowerUp(t) = self.wave_state {
-            if t > 0 {
-                WaveState::AnnoncePowerUp(t-1)
-            } else {
-                self.overlay.visible = false;
-                self.powerups = vec![
-                    PowerUp::new_fixed(Power::DamageUp, SIZE.times(Vector::new(0.25, 0.25))),
-                    PowerUp::new_fixed(Power::LifeUp, SIZE.times(Vector::new(0.75, 0.25))),
-                    PowerUp::new_fixed(Power::PierceUp, SIZE.times(Vector::new(0.25, 0.75))),
-                    PowerUp::new_fixed(Power::ShotUp, SIZE.times(Vector::new(0.75, 0.75))),
-                ];
-                WaveState::PowerUp
-            }
-        } else if self.wave_state == WaveState::PowerUp && self.powerups.len() <= 2 {
-            self.wave += 1;
-            self.powerups = vec![];
-            self.overlay = Overlay::wave(self.wave);
-            WaveState::AnnounceWave(60)
-        } else if let WaveState::AnnounceWave(t) = self.wave_state {
-            if t > 0 {
-                WaveState::AnnounceWave(t-1)
-            } else {
-                self.overlay.visible = false;
-                WaveState::Ongoing
-            }
-        } else {
-            self.wave_state
-        };
-
-        if self.wave_state == WaveState::Ongoing {
-            // Spawn enemies and powerups if needed
-            self.spawn_enemy();
-            self.spawn_powerup();
-        }
-
-        // Update and remove shots
-        for s in &mut self.shots {
-            s.update()
-        }
-        self.shots = self.shots
-            .iter()
-            .filter_map(|s| if s.alive { Some(s.clone()) } else { None })
-            .collect();
-
-        // Update and remove enemies
-        let mut enn = vec![];
-        let mut new_enn = vec![];
-        swap(&mut enn, &mut self.enemies);
-        self.enemies = enn
-            .iter()
-            .filter_map(|e| {
-                let mut e = e.clone();
-                new_enn.extend(e.update(self));
-
-                if e.alive() {
-                    Some(e)
-                } else {
-                    self.score += e.level * e.level;
-                    None
-                }
-            })
-            .collect();
-        self.enemies.extend(new_enn);
-
-        // Update powerups
-        for p in &mut self.powerups {
-            p.update(&mut self.player);
-        }
-        self.powerups = self.powerups
-            .iter()
-            .filter_map(|p| if p.hits > 0 { Some(p.clone()) } else { None })
-            .collect();
-
-        // Update the player
-        Player::update(mouse, self);
-
-        if self.player.life == 0 {
-            self.overlay = Overlay::game_over();
-        }
-    }
-
-    fn event(&mut self, event: Event, mouse: Vector) {
-        match event {
-            Event::PointerInput(p) => {
-                if p.is_down() {
-                    self.shots.extend(
-                        self.player.fire(mouse)
-                    );
-                }
-            },
-            Event::KeyboardInput(e) => {
-                if e.is_down() {
-                    match e.key() {
-                        Key::P => {
-                            self.toggle_pause();
-                        },
-                        Key::R => {
-                            self.restart();
-                        }
-                        _ => (),
-                    }
+impl Options {
+    fn from_args() -> Self {
+        let mut options = Options::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => options.seed = args.next().and_then(|s| s.parse().ok()),
+                "--record" => options.record = args.next(),
+                "--boids" => options.boids = true,
+                "--arena" => options.arena = true,
+                "--lang" => options.lang = args.next(),
+                "--replay" => {
+                    let path = args.next().expect("--replay needs a file");
+                    let data = std::fs::read(&path).expect("Could not read the replay");
+                    options.replay = Some(Replay::from_json(&data).unwrap_or_else(|e| panic!("{}: {}", path, e)));
                 }
+                _ => eprintln!("Unknown argument {}", arg),
             }
-            _ => ()
         }
+        options
     }
 
-    fn restart(&mut self) {
-        // Entities
-        self.player = Player::new();
-        self.enemies = vec![];
-        self.shots = vec![];
-        self.powerups = vec![];
-        // General
-        self.wave = 0;
-        self.wave_state = WaveState::PowerUp;
-        self.frame = 0;
-        self.paused = false;
-        self.score = 0;
-        self.bg = Background::new(&mut self.rng);
+    /// Boids are asked with `--boids` or `?boids=1` in the page URL
+    fn flocking(&self) -> Option<Flocking> {
+        let asked = self.boids || platform::query_param("boids").is_some_and(|b| b != "0");
+        asked.then(Flocking::default)
     }
 
-    fn toggle_pause(&mut self) {
-        
-        // No pause if dead
-        if self.player.life > 0 {
-            self.paused = !self.paused;
-            
-            if self.paused {
-                self.overlay = Overlay::pause();
-            } else {
-                self.overlay.visible = false;
-            }
-        }
+    /// The big arena is asked with `--arena` or `?arena=1` in the page URL
+    fn arena(&self) -> Option<BigArena> {
+        let asked = self.arena || platform::query_param("arena").is_some_and(|a| a != "0");
+        asked.then(BigArena::default)
     }
 
-    fn spawn_enemy(&mut self) {
-        if self.frame % 42 != 17 {return;}
-
-        // Find a position out of the screen
-        let x = Uniform::new(-100.0, SIZE.x + 100.0);
-        let y = Uniform::new(-100.0, SIZE.y + 100.0);
-        let mut pos = Vector::ZERO;
-        while in_screen(&pos) {
-            pos.x = x.sample(&mut self.rng);
-            pos.y = y.sample(&mut self.rng);
-        }
-
-        let unif = Uniform::new_inclusive(1, self.wave + 1);
-        let life = unif.sample(&mut self.rng);
-
-        self.enemies.push(
-            Enemy::new(pos, life as u32)
-        );
+    /// Asked with `--lang fr` or `?lang=fr`, then the `saved` one, or the one of the system
+    fn strings(&self, saved: Option<&str>) -> Strings {
+        let lang = self.lang.clone()
+            .or_else(|| platform::query_param("lang"))
+            .or_else(|| saved.map(str::to_string))
+            .or_else(platform::language)
+            .unwrap_or_default();
+        Strings::new(&lang)
     }
 
-    fn spawn_powerup(&mut self) {
-        let b = Bernoulli::from_ratio(1, 30 * POWERUP_DELAY).unwrap();
-        if b.sample(&mut self.rng) {
-            let &p = vec![
-                Power::LifeUp,
-                Power::LifeUp,
-                Power::LifeUp,
-                Power::LifeUp,
-                Power::ShotUp,
-                Power::PierceUp,
-                Power::DamageUp,
-            ].iter().choose(&mut self.rng).unwrap();
-            self.powerups.push(PowerUp::new(p, &mut self.rng));
-        }
+    /// The replay's seed, then the one asked on the command line or
+    /// in the page URL (`?seed=42`), or one derived from the time.
+    fn seed(&self) -> u64 {
+        self.replay.as_ref().map(|r| r.seed)
+            .or(self.seed)
+            .or_else(|| platform::query_param("seed").and_then(|s| s.parse().ok()))
+            .unwrap_or_else(|| platform::now_millis() % SEED_RANGE)
     }
 }
 
-
-
 fn main() {
+    let options = Options::from_args();
     run(
         Settings {
             size: SIZE,
@@ -392,34 +110,251 @@ fn main() {
             resizable: true,
             ..Settings::default()
         },
-        app,
+        move |window, gfx, input| app(window, gfx, input, options),
     );
 }
 
-async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()> {
-    let ttf = VectorFont::load("ThaleahFat.ttf").await?;
-    let font = ttf.to_renderer(&gfx, 72.0)?;
+/// The waves from `waves.json`, so they can be tuned without
+/// recompiling, or the default ones if it can not be used.
+async fn load_waves() -> WaveScript {
+    match load_file("waves.json").await {
+        Ok(data) => WaveScript::from_json(&data).unwrap_or_else(|e| {
+            log::error!("Invalid waves.json, using the default waves: {}", e);
+            WaveScript::default()
+        }),
+        Err(_) => WaveScript::default(),
+    }
+}
 
-    let mut game = Game::new(font);
+/// Where the pointer is in the virtual resolution. It stays on the
+/// edges when it is over the black bars.
+fn mouse_position(gfx: &Graphics, window: &Window, input: &Input) -> Vector {
+    gfx.screen_to_camera(window, input.mouse().location()).clamp(Vector::ZERO, SIZE)
+}
+
+/// On the web, fit the canvas to the room in the page, with
+/// the aspect ratio of the virtual resolution
+fn fit_page(window: &Window) {
+    if let Some(room) = platform::page_size() {
+        let scale = (room.x / SIZE.x).min(room.y / SIZE.y).max(0.25);
+        let size = SIZE * scale;
+        if (size - window.size()).len() > 1.0 {
+            window.set_size(size);
+        }
+    }
+}
+
+/// Apply an action to the game, and record it for the replay.
+fn act(game: &mut Game, recorder: &mut Recorder, action: Action, mouse: Vector) {
+    recorder.action(action, mouse);
+    game.action(action, mouse);
+}
+
+/// The keys that open the pause menu during a run
+fn opens_pause(event: &Event, bindings: &Bindings) -> bool {
+    match event {
+        Event::KeyboardInput(e) => e.is_down() && (e.key() == bindings.key(Command::Pause) || e.key() == Key::Escape),
+        _ => false,
+    }
+}
+
+/// The key that shows or hides the stat sheet, which does not change the run
+fn toggles_stats(event: &Event, bindings: &Bindings) -> bool {
+    match event {
+        Event::KeyboardInput(e) => e.is_down() && e.key() == bindings.key(Command::Stats),
+        _ => false,
+    }
+}
+
+/// How an event moves around the menus, `mouse` being where the pointer is now
+fn menu_input(event: &Event, mouse: Vector) -> Option<MenuInput> {
+    match event {
+        Event::KeyboardInput(e) if e.is_down() => match e.key() {
+            Key::Up | Key::W | Key::Left | Key::A => Some(MenuInput::Up),
+            Key::Down | Key::S | Key::Right | Key::D => Some(MenuInput::Down),
+            Key::Return | Key::Space => Some(MenuInput::Select),
+            Key::Escape | Key::Back => Some(MenuInput::Back),
+            _ => None,
+        },
+        Event::PointerMoved(_) => Some(MenuInput::Point(mouse)),
+        Event::PointerInput(e) if e.is_down() => Some(MenuInput::Click(mouse)),
+        _ => None,
+    }
+}
+
+/// Mute the music or the sounds, as chosen in the options
+fn set_volumes(mixer: &mut Mixer, options: &MenuOptions) {
+    let on = |b: bool| if b { 1.0 } else { 0.0 };
+    mixer.set_volume(Category::Music, on(options.music));
+    for &c in &[Category::Player, Category::Enemies, Category::Pickups] {
+        mixer.set_volume(c, on(options.sounds));
+    }
+}
+
+async fn app(window: Window, mut gfx: Graphics, mut input: Input, options: Options) -> Result<()> {
+    // Everything is drawn at the virtual resolution, and scaled to
+    // the window with black bars to keep the aspect ratio
+    gfx.set_camera_size(SIZE);
+    gfx.set_resize_handler(ResizeHandler::Fit { aspect_width: SIZE.x, aspect_height: SIZE.y });
+
+    let ttf = VectorFont::load("ThaleahFat.ttf").await?;
+    let fonts = Fonts::new(&ttf, &gfx)?;
+
+    // The command line and the page URL win over the saved settings
+    let saved = SavedSettings::load();
+    let strings = options.strings(saved.as_ref().map(|s| s.options.language.as_str()));
+    let mut settings = saved.unwrap_or_default();
+    settings.options.language = strings.lang.to_string();
+    settings.options.boids |= options.flocking().is_some();
+    settings.options.arena |= options.arena().is_some();
+
+    let mut rules = match &options.replay {
+        Some(replay) => replay.rules.clone(),
+        None => Rules {
+            waves: load_waves().await,
+            flocking: settings.options.boids.then(Flocking::default),
+            arena: settings.options.arena.then(BigArena::default),
+            controls: settings.options.controls,
+        },
+    };
+
+    let mut seed = options.seed();
+    let mut game = Game::with_rules(seed, rules.clone());
+    let mut renderer = Renderer::new(fonts, strings);
+    let mut recorder = Recorder::new(seed, rules.clone());
+    let mut scores = HighScores::new(Box::new(SavedStore));
+    let mut mixer = Mixer::new(platform::audio_backend());
+    set_volumes(&mut mixer, &settings.options);
+    let mut screens = Screens::new(settings);
+    let mut replay = options.replay.map(|r| r.ticks.into_iter());
+    if replay.is_some() {
+        screens.reset(&[Screen::InGame]);
+    }
 
     let mut update_timer = Timer::time_per_second(30.0);
     let mut draw_timer = Timer::time_per_second(60.0);
 
     let mut render_skip = 0;
 
-    
+
     // Game loop
     loop {
-        let mouse = gfx.screen_to_camera(&window, input.mouse().location());
+        fit_page(&window);
+        let mouse = mouse_position(&gfx, &window, &input);
+        let mut controls = screens.bindings.controls(mouse, |k| input.key_down(k));
+        controls.fire |= input.mouse().left();
+        controls.dash |= input.mouse().right();
 
         // Event handeling
         while let Some(event) = input.next_event().await {
-            game.event(event, mouse)
+            if replay.is_some() { continue; }
+            let mouse = mouse_position(&gfx, &window, &input);
+
+            if screens.top() == Screen::InGame {
+                if toggles_stats(&event, &screens.bindings) {
+                    renderer.show_stats = !renderer.show_stats;
+                } else if opens_pause(&event, &screens.bindings) {
+                    act(&mut game, &mut recorder, Action::TogglePause, mouse);
+                    screens.push(Screen::Pause);
+                } else if let Some(action) = Action::from_event(&event, &screens.bindings) {
+                    act(&mut game, &mut recorder, action, mouse);
+                }
+                continue;
+            }
+
+            // The next key pressed is the new binding
+            if screens.rebinding.is_some() {
+                if let Event::KeyboardInput(e) = &event {
+                    if e.is_down() && screens.bind(e.key()) {
+                        screens.settings().save();
+                    }
+                }
+                continue;
+            }
+
+            let item = menu_input(&event, mouse).and_then(|i| screens.input(i));
+            match item {
+                Some(MenuItem::Play) => {
+                    rules.flocking = screens.options.boids.then(Flocking::default);
+                    rules.arena = screens.options.arena.then(BigArena::default);
+                    rules.controls = screens.options.controls;
+                    game = Game::with_rules(seed, rules.clone());
+                    recorder = Recorder::new(seed, rules.clone());
+                    scores.last_rank = None;
+                    screens.reset(&[Screen::Title, Screen::InGame]);
+                    // The next run from the title screen gets another seed
+                    seed = platform::now_millis() % SEED_RANGE;
+                }
+                Some(MenuItem::Options) => screens.push(Screen::Options),
+                Some(MenuItem::HighScores) => screens.push(Screen::HighScores),
+                Some(MenuItem::HowToPlay) => screens.push(Screen::HowToPlay),
+                Some(MenuItem::Keys) => screens.push(Screen::Keys),
+                Some(MenuItem::Back) => screens.pop(),
+                Some(MenuItem::Resume) => {
+                    act(&mut game, &mut recorder, Action::TogglePause, mouse);
+                    screens.pop();
+                }
+                Some(MenuItem::Retry) => {
+                    act(&mut game, &mut recorder, Action::Restart, mouse);
+                    scores.last_rank = None;
+                    screens.reset(&[Screen::Title, Screen::InGame]);
+                }
+                Some(MenuItem::NewSeed) => {
+                    act(&mut game, &mut recorder, Action::NewSeed, mouse);
+                    scores.last_rank = None;
+                    screens.reset(&[Screen::Title, Screen::InGame]);
+                }
+                Some(MenuItem::Title) => screens.reset(&[Screen::Title]),
+                Some(MenuItem::Music) | Some(MenuItem::Sounds) => {
+                    set_volumes(&mut mixer, &screens.options);
+                    screens.settings().save();
+                }
+                Some(MenuItem::Language) => {
+                    renderer.strings = Strings::new(&screens.options.language);
+                    screens.settings().save();
+                }
+                Some(MenuItem::Boids) | Some(MenuItem::Arena) | Some(MenuItem::Controls) => screens.settings().save(),
+                Some(MenuItem::Bind(_)) | None => {}
+            }
         }
+        mixer.handle(&game.take_events());
 
         // We use a while loop rather than an if so that we can try to catch up in the event of having a slow down.
         while update_timer.tick() {
-            game.update(mouse);
+            // The game waits while on the title screen and its menus
+            if !screens.top().shows_game() {
+                mixer.music.state = MusicState::of(&game);
+                mixer.mix(SAMPLES_PER_TICK);
+                continue;
+            }
+
+            if let Some(ticks) = &mut replay {
+                if let Some(tick) = ticks.next() {
+                    tick.apply(&mut game);
+                }
+                mixer.handle(&game.take_events());
+                mixer.music.state = MusicState::of(&game);
+                mixer.mix(SAMPLES_PER_TICK);
+                continue;
+            }
+
+            let alive = game.player.life > 0;
+            recorder.tick(controls);
+            game.update(controls);
+            mixer.handle(&game.take_events());
+            mixer.music.state = MusicState::of(&game);
+            mixer.mix(SAMPLES_PER_TICK);
+
+            if alive && game.player.life == 0 {
+                scores.insert(ScoreEntry::new(&game, platform::now_millis() / 1000));
+                screens.push(Screen::GameOver);
+
+                if let Some(path) = &options.record {
+                    if let Err(e) = std::fs::write(path, recorder.replay(game.score).to_json()) {
+                        eprintln!("Could not save the replay to {}: {}", path, e);
+                    }
+                }
+            }
         }
 
         // Unlike the update cycle drawing doesn't change our state
@@ -433,7 +368,7 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
 
             let update_prop = update_timer.elapsed().as_secs_f32() / update_timer.period().as_secs_f32();
 
-            game.draw(&mut gfx, update_prop, render_skip);
+            renderer.draw(&mut gfx, &game, &scores, &screens, update_prop, render_skip);
             // Send the data to be drawn
             gfx.present(&window)?;
         }
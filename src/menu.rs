@@ -0,0 +1,408 @@
+//! The screens around the game and their menus.
+//!
+//! Screens are kept on a stack: the title at the bottom, then the game,
+//! then the pause menu for instance. Only the top one gets the inputs.
+//! Choosing an item returns it, and the app decides what it does to the
+//! game, so that everything that changes a run still goes through an
+//! `Action` and ends up in the replays.
+
+use quicksilver::{
+    geom::{Rectangle, Shape, Vector},
+    input::Key,
+};
+use serde::{Serialize, Deserialize};
+
+use super::{SIZE, ControlScheme, WaveScript};
+use crate::i18n::{Message, Msg, Strings};
+use crate::controls::{Bindings, Command, key_name};
+use crate::settings::Settings;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Screen {
+    Title,
+    Options,
+    HighScores,
+    HowToPlay,
+    /// Rebinding the keys
+    Keys,
+    InGame,
+    Pause,
+    GameOver,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MenuItem {
+    Play,
+    Options,
+    HighScores,
+    HowToPlay,
+    Resume,
+    /// The same seed again
+    Retry,
+    NewSeed,
+    /// Back to the title screen
+    Title,
+    /// Back to the previous screen
+    Back,
+    Boids,
+    Arena,
+    Music,
+    Sounds,
+    Language,
+    Controls,
+    Keys,
+    /// Choose a new key for the command
+    Bind(Command),
+}
+
+/// What can be changed in the options screen
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MenuOptions {
+    /// For the next runs
+    pub boids: bool,
+    /// For the next runs too
+    pub arena: bool,
+    pub music: bool,
+    pub sounds: bool,
+    /// The code of the locale, like "en"
+    pub language: String,
+    /// For the next runs
+    pub controls: ControlScheme,
+}
+
+impl Default for MenuOptions {
+    fn default() -> Self {
+        MenuOptions {
+            boids: false,
+            arena: false,
+            music: true,
+            sounds: true,
+            language: "en".to_string(),
+            controls: ControlScheme::default(),
+        }
+    }
+}
+
+const ITEM_SIZE: Vector = Vector { x: 420.0, y: 60.0 };
+
+impl Screen {
+    pub fn title(self) -> Option<Msg> {
+        match self {
+            Screen::Title => Some(Msg::GameTitle),
+            Screen::Options => Some(Msg::Options),
+            Screen::HighScores => Some(Msg::HighScores),
+            Screen::HowToPlay => Some(Msg::HowToPlay),
+            Screen::Keys => Some(Msg::Keys),
+            Screen::InGame => None,
+            Screen::Pause => Some(Msg::Paused),
+            Screen::GameOver => Some(Msg::GameOver),
+        }
+    }
+
+    pub fn items(self) -> &'static [MenuItem] {
+        match self {
+            Screen::Title => &[MenuItem::Play, MenuItem::Options, MenuItem::HighScores, MenuItem::HowToPlay],
+            Screen::Options => &[MenuItem::Boids, MenuItem::Arena, MenuItem::Music, MenuItem::Sounds, MenuItem::Language, MenuItem::Controls, MenuItem::Keys, MenuItem::Back],
+            Screen::Keys => &[
+                MenuItem::Bind(Command::MoveUp),
+                MenuItem::Bind(Command::MoveDown),
+                MenuItem::Bind(Command::MoveLeft),
+                MenuItem::Bind(Command::MoveRight),
+                MenuItem::Bind(Command::Fire),
+                MenuItem::Bind(Command::AimUp),
+                MenuItem::Bind(Command::AimDown),
+                MenuItem::Bind(Command::AimLeft),
+                MenuItem::Bind(Command::AimRight),
+                MenuItem::Bind(Command::Pause),
+                MenuItem::Bind(Command::Reroll),
+                MenuItem::Bind(Command::Dash),
+                MenuItem::Bind(Command::Stats),
+                MenuItem::Back,
+            ],
+            Screen::HighScores | Screen::HowToPlay => &[MenuItem::Back],
+            Screen::InGame => &[],
+            Screen::Pause => &[MenuItem::Resume, MenuItem::Retry, MenuItem::NewSeed, MenuItem::Title],
+            Screen::GameOver => &[MenuItem::Retry, MenuItem::NewSeed, MenuItem::Title],
+        }
+    }
+
+    /// What Escape does
+    fn back(self) -> Option<MenuItem> {
+        match self {
+            Screen::Title | Screen::InGame | Screen::GameOver => None,
+            Screen::Options | Screen::HighScores | Screen::HowToPlay | Screen::Keys => Some(MenuItem::Back),
+            Screen::Pause => Some(MenuItem::Resume),
+        }
+    }
+
+    /// Whether the game is drawn under the menu
+    pub fn shows_game(self) -> bool {
+        matches!(self, Screen::InGame | Screen::Pause | Screen::GameOver)
+    }
+
+    /// Where the `i`-th item is drawn and can be clicked
+    pub fn item_rect(self, i: usize) -> Rectangle {
+        let n = self.items().len() as f32;
+        let i = i as f32;
+        let pos = match self {
+            // In a row at the bottom, under the high scores
+            Screen::GameOver => Vector::new(
+                (SIZE.x - ITEM_SIZE.x * n) / 2.0 + ITEM_SIZE.x * i,
+                SIZE.y - ITEM_SIZE.y * 1.5,
+            ),
+            // At the bottom, under what the screen shows
+            Screen::HighScores | Screen::HowToPlay => Vector::new(
+                (SIZE.x - ITEM_SIZE.x) / 2.0,
+                SIZE.y - ITEM_SIZE.y * (n + 0.5 - i),
+            ),
+            // Under the title, which is at the top
+            Screen::Options => Vector::new((SIZE.x - ITEM_SIZE.x) / 2.0, 200.0 + ITEM_SIZE.y * i),
+            // In two columns, and back at the bottom
+            Screen::Keys if i < n - 1.0 => {
+                let rows = ((n - 1.0) / 2.0).ceil();
+                Vector::new(
+                    SIZE.x / 2.0 - ITEM_SIZE.x * (1.0 - (i / rows).floor()),
+                    200.0 + ITEM_SIZE.y * (i % rows),
+                )
+            }
+            Screen::Keys => Vector::new((SIZE.x - ITEM_SIZE.x) / 2.0, SIZE.y - ITEM_SIZE.y * 1.5),
+            // Under the title
+            _ => Vector::new((SIZE.x - ITEM_SIZE.x) / 2.0, SIZE.y / 2.0 + 100.0 + ITEM_SIZE.y * i),
+        };
+        Rectangle::new(pos, ITEM_SIZE)
+    }
+}
+
+/// What moves around the menus
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Select,
+    Back,
+    /// The mouse moved there
+    Point(Vector),
+    /// The mouse clicked there
+    Click(Vector),
+}
+
+pub struct Screens {
+    stack: Vec<Screen>,
+    /// Index of the highlighted item of the top screen
+    pub selected: usize,
+    pub options: MenuOptions,
+    pub bindings: Bindings,
+    /// Waiting for the new key of this command
+    pub rebinding: Option<Command>,
+}
+
+impl Screens {
+    pub fn new(settings: Settings) -> Self {
+        Screens {
+            stack: vec![Screen::Title],
+            selected: 0,
+            options: settings.options,
+            bindings: settings.bindings,
+            rebinding: None,
+        }
+    }
+
+    /// What is saved between sessions
+    pub fn settings(&self) -> Settings {
+        Settings {
+            options: self.options.clone(),
+            bindings: self.bindings.clone(),
+        }
+    }
+
+    /// Give the key pressed to the command being rebound, Escape keeps
+    /// the previous one. Returns whether the bindings changed.
+    pub fn bind(&mut self, key: Key) -> bool {
+        match self.rebinding.take() {
+            Some(command) if key != Key::Escape => self.bindings.bind(command, key),
+            _ => false,
+        }
+    }
+
+    pub fn top(&self) -> Screen {
+        *self.stack.last().unwrap()
+    }
+
+    pub fn push(&mut self, screen: Screen) {
+        self.stack.push(screen);
+        self.selected = 0;
+    }
+
+    /// Go back to the previous screen, there is always one left
+    pub fn pop(&mut self) {
+        if self.stack.len() > 1 {
+            self.stack.pop();
+        }
+        self.selected = 0;
+    }
+
+    /// Replace the whole stack
+    pub fn reset(&mut self, stack: &[Screen]) {
+        self.stack = stack.to_vec();
+        self.selected = 0;
+    }
+
+    /// The text of an item, with the value of the options
+    pub fn label(&self, item: MenuItem, strings: &Strings) -> String {
+        let on_off = |b: bool| strings.get(if b { Msg::On } else { Msg::Off }).to_string();
+        let message = match item {
+            MenuItem::Play => Msg::Play.into(),
+            MenuItem::Options => Msg::Options.into(),
+            MenuItem::HighScores => Msg::HighScores.into(),
+            MenuItem::HowToPlay => Msg::HowToPlay.into(),
+            MenuItem::Resume => Msg::Resume.into(),
+            MenuItem::Retry => Msg::Retry.into(),
+            MenuItem::NewSeed => Msg::NewSeed.into(),
+            MenuItem::Title => Msg::TitleScreen.into(),
+            MenuItem::Back => Msg::Back.into(),
+            MenuItem::Boids => Message::new(Msg::Boids).arg(on_off(self.options.boids)),
+            MenuItem::Arena => Message::new(Msg::BigArena).arg(on_off(self.options.arena)),
+            MenuItem::Music => Message::new(Msg::Music).arg(on_off(self.options.music)),
+            MenuItem::Sounds => Message::new(Msg::Sounds).arg(on_off(self.options.sounds)),
+            MenuItem::Language => Message::new(Msg::Language).arg(strings.get(Msg::LanguageName)),
+            MenuItem::Controls => Message::new(Msg::Controls).arg(strings.get(scheme_name(self.options.controls))),
+            MenuItem::Keys => Msg::Keys.into(),
+            MenuItem::Bind(command) => {
+                let key = if self.rebinding == Some(command) {
+                    strings.get(Msg::PressKey)
+                } else {
+                    key_name(self.bindings.key(command)).unwrap_or("?")
+                };
+                Message::new(Msg::Binding).arg(strings.get(command_name(command))).arg(key)
+            }
+        };
+        strings.text(&message)
+    }
+
+    /// The how to play text, with the keys and scheme of the options
+    /// and the bosses of `waves`
+    pub fn how_to_play(&self, strings: &Strings, waves: &WaveScript) -> String {
+        let keys = |commands: [Command; 4]| {
+            commands.iter().map(|&c| key_name(self.bindings.key(c)).unwrap_or("?")).collect::<Vec<_>>().join("/")
+        };
+        let movement = keys([Command::MoveUp, Command::MoveLeft, Command::MoveDown, Command::MoveRight]);
+        let controls = match self.options.controls {
+            ControlScheme::MouseFollow => Message::new(Msg::HowToMoveMouse),
+            ControlScheme::KeysAndMouse => Message::new(Msg::HowToMoveKeysAndMouse).arg(movement),
+            ControlScheme::KeysOnly => Message::new(Msg::HowToMoveKeys)
+                .arg(movement)
+                .arg(keys([Command::AimUp, Command::AimLeft, Command::AimDown, Command::AimRight]))
+                .arg(key_name(self.bindings.key(Command::Fire)).unwrap_or("?")),
+        };
+        let bosses = match &waves.boss {
+            Some(boss) if boss.every > 0 => strings.text(&Message::new(Msg::BossEvery).arg(boss.every)),
+            _ => String::new(),
+        };
+        strings.text(
+            &Message::new(Msg::HowToPlayText)
+                .arg(strings.text(&controls))
+                .arg(bosses)
+                .arg(key_name(self.bindings.key(Command::Pause)).unwrap_or("?"))
+        )
+    }
+
+    fn item_at(&self, pos: Vector) -> Option<usize> {
+        let screen = self.top();
+        (0..screen.items().len()).find(|&i| screen.item_rect(i).contains(pos))
+    }
+
+    /// Move around the menu of the top screen. Returns the chosen item,
+    /// the options are already toggled when it is one of them.
+    pub fn input(&mut self, input: MenuInput) -> Option<MenuItem> {
+        let screen = self.top();
+        let items = screen.items();
+        if items.is_empty() {
+            return None;
+        }
+
+        let chosen = match input {
+            MenuInput::Up => {
+                self.selected = (self.selected + items.len() - 1) % items.len();
+                None
+            }
+            MenuInput::Down => {
+                self.selected = (self.selected + 1) % items.len();
+                None
+            }
+            MenuInput::Select => items.get(self.selected).copied(),
+            MenuInput::Back => screen.back(),
+            MenuInput::Point(pos) => {
+                if let Some(i) = self.item_at(pos) {
+                    self.selected = i;
+                }
+                None
+            }
+            MenuInput::Click(pos) => self.item_at(pos).map(|i| items[i]),
+        };
+
+        match chosen {
+            Some(MenuItem::Boids) => self.options.boids = !self.options.boids,
+            Some(MenuItem::Arena) => self.options.arena = !self.options.arena,
+            Some(MenuItem::Music) => self.options.music = !self.options.music,
+            Some(MenuItem::Sounds) => self.options.sounds = !self.options.sounds,
+            Some(MenuItem::Language) => self.options.language = Strings::new(&self.options.language).next().lang.to_string(),
+            Some(MenuItem::Controls) => self.options.controls = self.options.controls.next(),
+            Some(MenuItem::Bind(command)) => self.rebinding = Some(command),
+            _ => {}
+        }
+        chosen
+    }
+}
+
+fn scheme_name(scheme: ControlScheme) -> Msg {
+    match scheme {
+        ControlScheme::MouseFollow => Msg::SchemeMouse,
+        ControlScheme::KeysAndMouse => Msg::SchemeKeysAndMouse,
+        ControlScheme::KeysOnly => Msg::SchemeKeys,
+    }
+}
+
+fn command_name(command: Command) -> Msg {
+    match command {
+        Command::MoveUp => Msg::MoveUp,
+        Command::MoveDown => Msg::MoveDown,
+        Command::MoveLeft => Msg::MoveLeft,
+        Command::MoveRight => Msg::MoveRight,
+        Command::AimUp => Msg::AimUp,
+        Command::AimDown => Msg::AimDown,
+        Command::AimLeft => Msg::AimLeft,
+        Command::AimRight => Msg::AimRight,
+        Command::Fire => Msg::Fire,
+        Command::Pause => Msg::Pause,
+        Command::Reroll => Msg::Reroll,
+        Command::Dash => Msg::Dash,
+        Command::Stats => Msg::Stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn how_to_play_follows_the_settings() {
+        let strings = Strings::new("en");
+        let mut waves = WaveScript::default();
+        let mut screens = Screens::new(Settings::default());
+
+        let text = screens.how_to_play(&strings, &waves);
+        assert!(text.starts_with("Move the mouse"));
+        assert!(text.contains("\nP or Escape to pause."));
+        let every = waves.boss.as_ref().unwrap().every;
+        assert!(text.contains(&format!("A boss comes every {} waves.", every)));
+
+        screens.options.controls = ControlScheme::KeysOnly;
+        screens.bindings.bind(Command::Pause, Key::Q);
+        waves.boss = None;
+        let text = screens.how_to_play(&strings, &waves);
+        assert!(text.starts_with("W/A/S/D to move, Up/Left/Down/Right to aim, Space to fire."));
+        assert!(text.contains("\nQ or Escape to pause."));
+        assert!(!text.contains("boss"));
+    }
+}
@@ -0,0 +1,339 @@
+//! Generative music that follows how chaotic the game is.
+//!
+//! A small sequencer plays a four bar loop on a few layers: a pad, a bass,
+//! drums, hats and an arpeggiated lead. The more chaos, the faster it goes
+//! and the more layers join. Between waves only the calm layers stay, and
+//! a short falling stinger plays when the player dies. Everything is
+//! rendered into sample buffers, there is no audio device involved.
+
+use std::f32::consts::PI;
+
+use rand::prelude::*;
+use rand_xorshift::XorShiftRng;
+
+use super::{Game, WaveState};
+use crate::sfx::SAMPLE_RATE;
+
+/// Sixteenth notes
+const STEPS_PER_BEAT: u32 = 4;
+const STEPS_PER_BAR: u32 = 16;
+const CALM_TEMPO: f32 = 80.0;
+const MIN_TEMPO: f32 = 100.0;
+const MAX_TEMPO: f32 = 160.0;
+/// A2, the root of the loop
+const ROOT: f32 = 110.0;
+/// The root of each bar, in semitones from `ROOT`: Am, F, C, G
+const PROGRESSION: [i32; 4] = [0, -4, 3, -2];
+/// Minor pentatonic, for the lead
+const SCALE: [i32; 6] = [0, 3, 5, 7, 10, 12];
+/// Seconds for a layer to fade in or out
+const FADE: f32 = 1.5;
+/// Seconds between the notes of the stinger
+const STINGER_STEP: f32 = 0.12;
+/// Of the whole mix, which leaves room for the sound effects
+const VOLUME: f32 = 0.45;
+
+/// What the music follows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MusicState {
+    /// From 0, a quiet start, to 1, a screen full of enemies, a huge score or a boss
+    pub chaos: f32,
+    /// Between the waves
+    pub calm: bool,
+    pub game_over: bool,
+}
+
+impl MusicState {
+    pub fn of(game: &Game) -> Self {
+        // The score counts on the same log scale as the background wiggle
+        let score = ((game.score as f32 + 2.0).log2() / 16.0).min(1.0);
+        let crowd = (game.enemies.len() as f32 / 40.0).min(1.0);
+        // Boss fights get everything
+        let boss = game.wave_state == WaveState::BossFight;
+        MusicState {
+            chaos: if boss { 1.0 } else { 0.6 * score + 0.4 * crowd },
+            calm: matches!(
+                game.wave_state,
+                WaveState::AnnoncePowerUp(_) | WaveState::PowerUp | WaveState::AnnounceWave(_)
+            ),
+            game_over: game.player.life == 0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Layer {
+    Pad,
+    Bass,
+    Drums,
+    Hats,
+    Lead,
+    Stinger,
+}
+
+const LAYERS: usize = 6;
+
+impl Layer {
+    /// How loud the layer should be for this state
+    fn target(self, state: &MusicState) -> f32 {
+        if state.game_over {
+            return if self == Layer::Stinger { 1.0 } else { 0.0 };
+        }
+        let chaos = state.chaos;
+        let above = |t: f32| if state.calm || chaos < t { 0.0 } else { 1.0 };
+        match self {
+            Layer::Pad => if state.calm { 1.0 } else { 0.8 - 0.5 * chaos },
+            Layer::Bass => if state.calm { 0.5 } else { 1.0 },
+            Layer::Drums => above(0.15),
+            Layer::Hats => above(0.35) * 0.8,
+            Layer::Lead => above(0.6) * 0.7,
+            Layer::Stinger => 1.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Note {
+    layer: Layer,
+    freq: f32,
+    /// In samples, negative until it starts
+    age: i64,
+    /// In samples
+    len: i64,
+    volume: f32,
+}
+
+impl Note {
+    fn sample(&self, rng: &mut XorShiftRng) -> f32 {
+        let t = self.age as f32 / SAMPLE_RATE as f32;
+        let len = self.len as f32 / SAMPLE_RATE as f32;
+        let sine = |f: f32| (2.0 * PI * f * t).sin();
+
+        let x = match self.layer {
+            Layer::Pad => {
+                let env = (t / 0.3).min(1.0) * ((len - t) / 0.3).clamp(0.0, 1.0);
+                env * (sine(self.freq) + 0.3 * sine(2.0 * self.freq))
+            }
+            Layer::Bass => {
+                let env = (-t * 4.0).exp();
+                env * (sine(self.freq) + 0.3 * sine(self.freq).signum())
+            }
+            Layer::Drums => {
+                // A kick, a sine falling from 150 to 40 Hz
+                let phase = 40.0 * t + 110.0 * (1.0 - (-t * 30.0).exp()) / 30.0;
+                (-t * 10.0).exp() * (2.0 * PI * phase).sin()
+            }
+            Layer::Hats => (-t * 60.0).exp() * rng.gen_range(-1.0, 1.0),
+            Layer::Lead => (-t * 8.0).exp() * 0.5 * sine(self.freq).signum(),
+            Layer::Stinger => (-t * 3.0).exp() * 0.5 * (sine(self.freq).signum() + sine(self.freq / 2.0)),
+        };
+        x * self.volume
+    }
+}
+
+pub struct Music {
+    pub state: MusicState,
+    rng: XorShiftRng,
+    notes: Vec<Note>,
+    levels: [f32; LAYERS],
+    /// Sixteenths played since the start
+    step: u32,
+    /// Samples until the next sixteenth
+    next_step: f32,
+    stinger_played: bool,
+}
+
+impl Default for Music {
+    fn default() -> Self {
+        Music::new(0)
+    }
+}
+
+impl Music {
+    /// The same seed and states always give the same samples
+    pub fn new(seed: u64) -> Self {
+        Music {
+            state: MusicState {
+                chaos: 0.0,
+                calm: true,
+                game_over: false,
+            },
+            rng: XorShiftRng::seed_from_u64(seed),
+            notes: vec![],
+            levels: [0.0; LAYERS],
+            step: 0,
+            next_step: 0.0,
+            stinger_played: false,
+        }
+    }
+
+    /// Beats per minute
+    pub fn tempo(&self) -> f32 {
+        if self.state.calm || self.state.game_over {
+            CALM_TEMPO
+        } else {
+            MIN_TEMPO + (MAX_TEMPO - MIN_TEMPO) * self.state.chaos.min(1.0)
+        }
+    }
+
+    fn play(&mut self, layer: Layer, semitones: i32, steps: f32, volume: f32) {
+        let step_len = SAMPLE_RATE as f32 * 60.0 / self.tempo() / STEPS_PER_BEAT as f32;
+        self.notes.push(Note {
+            layer,
+            freq: ROOT * (semitones as f32 / 12.0).exp2(),
+            age: 0,
+            len: (steps * step_len) as i64,
+            volume,
+        });
+    }
+
+    /// Start the notes of the next sixteenth
+    fn sequence(&mut self) {
+        let in_bar = self.step % STEPS_PER_BAR;
+        let bar = (self.step / STEPS_PER_BAR) as usize;
+        let root = PROGRESSION[bar % PROGRESSION.len()];
+        let chaos = self.state.chaos;
+        self.step += 1;
+
+        if self.state.game_over {
+            return;
+        }
+
+        if in_bar == 0 {
+            for &interval in &[0, 3, 7] {
+                self.play(Layer::Pad, root + 12 + interval, STEPS_PER_BAR as f32, 0.15);
+            }
+        }
+        let bass_steps: &[u32] = if self.state.calm { &[0, 8] } else { &[0, 6, 8, 14] };
+        if bass_steps.contains(&in_bar) {
+            let octave = if in_bar == 8 { 12 } else { 0 };
+            self.play(Layer::Bass, root - 12 + octave, 4.0, 0.35);
+        }
+        if in_bar.is_multiple_of(4) || (chaos > 0.7 && (in_bar == 10 || in_bar == 14)) {
+            self.play(Layer::Drums, 0, 4.0, 0.6);
+        }
+        if in_bar.is_multiple_of(2) || chaos > 0.8 {
+            let accent = if in_bar % 4 == 2 { 0.25 } else { 0.12 };
+            self.play(Layer::Hats, 0, 1.0, accent);
+        }
+        if self.rng.gen_bool(0.6) {
+            let &degree = SCALE.choose(&mut self.rng).unwrap();
+            self.play(Layer::Lead, root + 24 + degree, 2.0, 0.15);
+        }
+    }
+
+    /// A falling arpeggio, once per game over
+    fn stinger(&mut self) {
+        let gap = (STINGER_STEP * SAMPLE_RATE as f32) as i64;
+        for (i, &degree) in SCALE.iter().rev().enumerate() {
+            self.notes.push(Note {
+                layer: Layer::Stinger,
+                freq: ROOT * 2.0 * (degree as f32 / 12.0).exp2(),
+                age: -gap * i as i64,
+                len: SAMPLE_RATE as i64,
+                volume: 0.3,
+            });
+        }
+    }
+
+    /// The next `len` samples of music
+    pub fn render(&mut self, len: usize) -> Vec<f32> {
+        if self.state.game_over && !self.stinger_played {
+            self.stinger();
+            self.stinger_played = true;
+        } else if !self.state.game_over {
+            self.stinger_played = false;
+        }
+
+        let layers = [Layer::Pad, Layer::Bass, Layer::Drums, Layer::Hats, Layer::Lead, Layer::Stinger];
+        let targets = layers.map(|l| l.target(&self.state));
+        let fade = 1.0 / (FADE * SAMPLE_RATE as f32);
+        let step_len = SAMPLE_RATE as f32 * 60.0 / self.tempo() / STEPS_PER_BEAT as f32;
+
+        let mut out = Vec::with_capacity(len);
+        for _ in 0..len {
+            if self.next_step <= 0.0 {
+                self.sequence();
+                self.next_step += step_len;
+            }
+            self.next_step -= 1.0;
+
+            for (level, target) in self.levels.iter_mut().zip(&targets) {
+                *level += (target - *level).clamp(-fade, fade);
+            }
+
+            let mut sample = 0.0;
+            for note in &mut self.notes {
+                if note.age >= 0 {
+                    let level = self.levels[note.layer as usize];
+                    sample += note.sample(&mut self.rng) * level;
+                }
+                note.age += 1;
+            }
+            out.push((sample * VOLUME).clamp(-1.0, 1.0));
+
+            if out.len() % 1024 == 0 {
+                self.notes.retain(|n| n.age < n.len);
+            }
+        }
+        self.notes.retain(|n| n.age < n.len);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(game: impl FnOnce(&mut Game)) -> MusicState {
+        let mut g = Game::new();
+        game(&mut g);
+        MusicState::of(&g)
+    }
+
+    /// A few seconds of music in `state`, and how loud each layer ends up
+    fn play(state: MusicState) -> (Vec<f32>, [f32; LAYERS]) {
+        let mut music = Music::new(1);
+        music.state = state;
+        let samples = music.render(3 * SAMPLE_RATE as usize);
+        (samples, music.levels)
+    }
+
+    #[test]
+    fn deterministic() {
+        let state = MusicState { chaos: 0.8, calm: false, game_over: false };
+        assert_eq!(play(state), play(state));
+    }
+
+    #[test]
+    fn states() {
+        let calm = state(|g| g.wave_state = WaveState::PowerUp);
+        let boss = state(|g| g.wave_state = WaveState::BossFight);
+        let over = state(|g| g.player.life = 0);
+        assert!(calm.calm && !boss.calm && over.game_over);
+        assert_eq!(boss.chaos, 1.0);
+
+        let (calm_out, calm_levels) = play(calm);
+        let (boss_out, boss_levels) = play(boss);
+        let (over_out, over_levels) = play(over);
+        assert_ne!(calm_out, boss_out);
+        assert_ne!(calm_out, over_out);
+        assert_ne!(boss_out, over_out);
+
+        let level = |levels: [f32; LAYERS], layer: Layer| levels[layer as usize];
+        // Only the calm layers between waves
+        assert_eq!(level(calm_levels, Layer::Pad), 1.0);
+        for layer in [Layer::Drums, Layer::Hats, Layer::Lead] {
+            assert_eq!(level(calm_levels, layer), 0.0, "{:?}", layer);
+            assert!(level(boss_levels, layer) > 0.5, "{:?}", layer);
+        }
+        // Nothing but the stinger once dead
+        for layer in [Layer::Pad, Layer::Bass, Layer::Drums, Layer::Hats, Layer::Lead, Layer::Stinger] {
+            let expected = if layer == Layer::Stinger { 1.0 } else { 0.0 };
+            assert_eq!(level(over_levels, layer), expected, "{:?}", layer);
+        }
+
+        let tempo = |state| Music { state, ..Music::default() }.tempo();
+        assert!(tempo(boss) > tempo(calm));
+    }
+}
@@ -1,9 +1,12 @@
-use quicksilver::geom::{Vector, Rectangle };
-use quicksilver::graphics::{Color, Graphics, FontRenderer};
+use quicksilver::geom::Vector;
+use quicksilver::graphics::Color;
 use super::{SIZE, Particle, Shape};
+use crate::i18n::{Message, Msg};
 
 pub struct Overlay {
-    pub text: String,
+    pub text: Message,
+    /// A smaller line under the text
+    pub subtitle: Option<Message>,
     pub color: Color,
     pub visible: bool,
     pub height: f32,
@@ -11,19 +14,21 @@ pub struct Overlay {
 }
 
 impl Overlay {
-    pub fn game_over() -> Self {
+    pub fn game_over(seed: u64) -> Self {
         Overlay {
-            text: String::from("GAME OVER!"),
+            text: Msg::GameOver.into(),
+            subtitle: Some(Message::new(Msg::Seed).arg(seed)),
             color: Color::RED,
             visible: true,
-            height: 120.0,
+            height: 160.0,
             frame: 0,
         }
     }
 
     pub fn pause() -> Self {
         Overlay {
-            text: String::from("Paused"),
+            text: Msg::Paused.into(),
+            subtitle: None,
             color: Color::YELLOW,
             visible: true,
             height: 120.0,
@@ -31,9 +36,10 @@ impl Overlay {
         }
     }
 
-    pub fn powerup() -> Self {
+    pub fn powerup(picks: usize) -> Self {
         Overlay {
-            text: String::from("Pick two"),
+            text: Message::new(Msg::PickPowerups).arg(picks),
+            subtitle: None,
             color: Color::GREEN,
             visible:true,
             height: 120.0,
@@ -41,9 +47,21 @@ impl Overlay {
         }
     }
 
+    pub fn boss(nb: u32) -> Self {
+        Overlay {
+            text: Message::new(Msg::BossWave).arg(nb),
+            subtitle: None,
+            color: Color::RED,
+            visible: true,
+            height: 120.0,
+            frame: 0,
+        }
+    }
+
     pub fn wave(nb: u32) -> Self {
         Overlay {
-            text: format!("Wave {}", nb),
+            text: Message::new(Msg::Wave).arg(nb),
+            subtitle: None,
             color: Color::ORANGE,
             visible: true,
             height: 120.0,
@@ -51,7 +69,8 @@ impl Overlay {
         }
     }
     
-    pub fn particles(&mut self) -> Vec<Particle> {
+    /// Shards along the banner, the view starting at `view` in the arena
+    pub fn particles(&mut self, view: Vector) -> Vec<Particle> {
         if !self.visible { return vec![]; }
 
         self.frame += 1;
@@ -60,14 +79,14 @@ impl Overlay {
         if self.frame % 6 < 1 {
             vec![
                 Particle {
-                    pos: Vector::new(0.0, (SIZE.y + self.height) / 2.0),
+                    pos: view + Vector::new(0.0, (SIZE.y + self.height) / 2.0),
                     speed: s,
                     shape: Shape::Shard(size / s, 2.5, true),
                     color: self.color,
                     ..Particle::default()
                 }, 
                 Particle {
-                    pos: Vector::new(SIZE.x, (SIZE.y - self.height) / 2.0),
+                    pos: view + Vector::new(SIZE.x, (SIZE.y - self.height) / 2.0),
                     speed: s,
                     angle: 180.0,
                     shape: Shape::Shard(size / s, 2.5, true),
@@ -79,24 +98,4 @@ impl Overlay {
         } else { vec![] }
     }
 
-
-    pub fn draw(&self, gfx: &mut Graphics, font: &mut FontRenderer) {
-        if !self.visible { return; }
-
-        let w = 36.0 * self.text.len() as f32;
-        let h = 40.0;
-
-        let rect = Rectangle::new(
-            Vector::new(0.0, SIZE.y / 2.0 - self.height / 2.0),
-            Vector::new(SIZE.x, self.height),
-        );
-        gfx.fill_rect(&rect, Color::WHITE.with_alpha(0.2));
-
-        font.draw(
-            gfx, 
-            &self.text,
-            self.color,
-            Vector::new(SIZE.x / 2.0 - w / 2.0, SIZE.y / 2.0 + h / 2.0),
-        ).unwrap();
-    }
-}
\ No newline at end of file
+}
@@ -1,5 +1,5 @@
-use quicksilver::geom::{Vector, Circle};
-use quicksilver::graphics::{Color, Graphics, Vertex, Mesh, Element};
+use quicksilver::geom::Vector;
+use quicksilver::graphics::Color;
 
 #[derive(Copy, Clone, Debug)]
 pub enum Shape {
@@ -57,59 +57,4 @@ impl Particle {
             && self.pos.y < 5000.0
 
     }
-
-    pub fn draw(&self, gfx: &mut Graphics, prop: f32) {
-        match self.shape {
-            Shape::Circle(size) => {
-                gfx.fill_circle(
-                    &Circle::new(
-                        self.pos + Vector::from_angle(self.angle) * (self.speed * prop),
-                        size * self.speed / 3.3), 
-                    self.color.with_alpha(self.speed / self.alpha_scale),
-                );
-            }
-            Shape::Shard(size, ratio, use_color) => {
-                let vertices = {
-                    let vel = Vector::from_angle(self.angle) * self.speed * size;
-                    let cross = Vector::new(-vel.y, vel.x);
-
-                    let front = Vertex {
-                        pos: self.pos + vel,
-                        uv: None,
-                        color: if use_color { self.color } else { Color::GREEN },
-                    };
-                    let left = Vertex {
-                        pos: self.pos + cross,
-                        uv: None,
-                        color: if use_color { self.color } else { Color::RED },
-                    };
-                    let right = Vertex {
-                        pos: self.pos - cross,
-                        uv: None,
-                        color: if use_color { self.color } else { Color::ORANGE } ,
-                    };
-                    let back = Vertex {
-                        pos: self.pos - vel * ratio,
-                        uv: None,
-                        color: if use_color { self.color } else { Color::BLUE.with_alpha(0.0) },
-                    };
-
-                    vec![front, left, right, back]
-                };
-                // A triangle is simply a pointer to indices of the vertices
-                let elements = vec![
-                    Element::Triangle([0, 1, 2]),
-                    Element::Triangle([1, 2, 3]),
-                ];
-                // Bring the vertices and the triangle elements together to define a mesh
-                let mesh = Mesh {
-                    vertices,
-                    elements,
-                    image: None,
-                };
-                // Pass a reference to the mesh to the graphics object to draw
-                gfx.draw_mesh(&mesh);
-            }
-        }
-    }
-}
\ No newline at end of file
+}
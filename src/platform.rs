@@ -0,0 +1,109 @@
+//! The few things that differ between the desktop and the web build.
+
+/// Milliseconds since the unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_millis() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Milliseconds since the unix epoch.
+#[cfg(target_arch = "wasm32")]
+pub fn now_millis() -> u64 {
+    stdweb::web::Date::now() as u64
+}
+
+/// The value of `name` in the query string of the page, like `?seed=42`.
+/// There is no page on desktop so it is always `None` there.
+#[cfg(target_arch = "wasm32")]
+pub fn query_param(name: &str) -> Option<String> {
+    let search = stdweb::web::window().location()?.search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| {
+            let mut kv = pair.splitn(2, '=');
+            if kv.next()? == name { kv.next().map(String::from) } else { None }
+        })
+}
+
+/// The value of `name` in the query string of the page, like `?seed=42`.
+/// There is no page on desktop so it is always `None` there.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn query_param(_name: &str) -> Option<String> {
+    None
+}
+
+/// Room for the game in the page: its width, and the height of the
+/// browser window so the whole game can be scrolled into view.
+#[cfg(target_arch = "wasm32")]
+pub fn page_size() -> Option<quicksilver::geom::Vector> {
+    use std::convert::TryInto;
+    let size: Vec<f64> = stdweb::js!(
+        return [document.body.clientWidth, window.innerHeight];
+    ).try_into().ok()?;
+    Some(quicksilver::geom::Vector::new(size[0] as f32, size[1] as f32))
+}
+
+/// There is no page on desktop, the window is resized by the user.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn page_size() -> Option<quicksilver::geom::Vector> {
+    None
+}
+
+/// The language of the user, like "fr-FR", from the browser.
+#[cfg(target_arch = "wasm32")]
+pub fn language() -> Option<String> {
+    stdweb::js!(return navigator.language || null;).into_string()
+}
+
+/// The language of the user, like "fr_FR.UTF-8", from the environment.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn language() -> Option<String> {
+    std::env::var("LANG").ok()
+}
+
+/// Where the sounds go: nowhere on desktop, as quicksilver has no audio.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn audio_backend() -> Box<dyn crate::audio::AudioBackend> {
+    Box::new(crate::audio::NullBackend::default())
+}
+
+/// Where the sounds go: the Web Audio API of the page.
+#[cfg(target_arch = "wasm32")]
+pub fn audio_backend() -> Box<dyn crate::audio::AudioBackend> {
+    Box::new(WebAudio)
+}
+
+/// Queues each mixed chunk right after the previous one.
+#[cfg(target_arch = "wasm32")]
+struct WebAudio;
+
+#[cfg(target_arch = "wasm32")]
+impl crate::audio::AudioBackend for WebAudio {
+    fn write(&mut self, samples: &[f32]) {
+        let samples = stdweb::web::TypedArray::<f32>::from(samples);
+        stdweb::js! { @(no_return)
+            const chaos = window.chaosAudio || (window.chaosAudio = {
+                ctx: new (window.AudioContext || window.webkitAudioContext)(),
+                next: 0,
+            });
+            const ctx = chaos.ctx;
+            // Browsers only start the sound after a click
+            if (ctx.state === "suspended") { ctx.resume(); }
+
+            const samples = @{samples};
+            const buffer = ctx.createBuffer(1, samples.length, @{crate::sfx::SAMPLE_RATE});
+            buffer.getChannelData(0).set(samples);
+            const source = ctx.createBufferSource();
+            source.buffer = buffer;
+            source.connect(ctx.destination);
+
+            chaos.next = Math.max(chaos.next, ctx.currentTime + 0.05);
+            source.start(chaos.next);
+            chaos.next += buffer.duration;
+        }
+    }
+}
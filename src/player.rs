@@ -4,10 +4,33 @@ use quicksilver::{
 use rand_distr::*;
 use rand_xorshift::XorShiftRng;
 
-use super::{Particle, Shape, hsv2rgb, Shot, Power, Game};
+use super::{Particle, Shape, hsv2rgb, Shot, Faction, Power, Game, GameEvent, Controls, ControlScheme, Status, Statuses, Evolution};
+use crate::powers::SLOWMO_FRAMES;
 
 pub const SHOT_SPEED: f32 = 45.0;
-const SHOT_DELAY: u32 = 5;
+/// Ticks it can not be hurt after a hit, 2/3 of a second
+pub const HURT_INVINCIBLE: i32 = 30;
+/// Ticks between two volleys at the start of a run
+const FIRE_DELAY: u32 = 6;
+/// The fastest it can fire, however many fire rate powerups it took
+const MIN_FIRE_DELAY: u32 = 2;
+/// Per tick, when moving with the keys
+const KEYS_SPEED: f32 = 12.0;
+const DASH_SPEED: f32 = 40.0;
+/// How long a dash lasts, it can not be hurt meanwhile
+const DASH_FRAMES: u32 = 6;
+/// Ticks before the next dash, less for each dash powerup after the first
+const DASH_RELOAD: u32 = 60;
+const MIN_DASH_RELOAD: u32 = 20;
+/// Added to the radius of the shots by each bigger shots powerup
+const SHOT_GROWTH: f32 = 0.35;
+/// How far the shield shards turn around the player
+const SHIELD_DISTANCE: f32 = 75.0;
+pub const SHARD_RADIUS: f32 = 12.0;
+/// Degrees per tick
+const SHIELD_SPEED: f32 = 8.0;
+/// Degrees per tick that homing shots turn, for each homing powerup
+const HOMING_TURN: f32 = 3.0;
 
 #[derive(Debug)]
 pub struct Player {
@@ -18,7 +41,34 @@ pub struct Player {
     pub shots: i32,
     pub pierce: i32,
     pub damage: i32,
-    pub shoot_delay: u32,
+    /// Ticks between two volleys
+    pub fire_delay: u32,
+    /// Ticks before it can fire again
+    pub cooldown: u32,
+    /// Where it fires, from its position
+    pub aim: Vector,
+    /// Every power taken, in order
+    pub powers: Vec<Power>,
+    /// Tokens left to draw the offers again
+    pub rerolls: u32,
+    /// How many of each power that does more than changing a number it has
+    pub homing: u32,
+    pub bounces: u32,
+    pub shields: u32,
+    pub dash: u32,
+    pub slowmo: u32,
+    pub chain: u32,
+    pub magnet: u32,
+    pub shot_size: u32,
+    /// Ticks left in the current dash
+    pub dashing: u32,
+    pub dash_dir: Vector,
+    /// Ticks before it can dash again
+    pub dash_cooldown: u32,
+    /// The timed powers it has
+    pub statuses: Statuses,
+    /// Unlocked by combinations of powers, in order
+    pub evolutions: Vec<Evolution>,
 }
 
 
@@ -32,41 +82,123 @@ impl Player {
             shots: 1,
             pierce: 1,
             damage: 1,
-            shoot_delay: SHOT_DELAY,
+            fire_delay: FIRE_DELAY,
+            cooldown: 0,
+            aim: Vector::X,
+            powers: vec![],
+            rerolls: 0,
+            homing: 0,
+            bounces: 0,
+            shields: 0,
+            dash: 0,
+            slowmo: 0,
+            chain: 0,
+            magnet: 0,
+            shot_size: 0,
+            dashing: 0,
+            dash_dir: Vector::ZERO,
+            dash_cooldown: 0,
+            statuses: Statuses::default(),
+            evolutions: vec![],
         }
     }
 
-    pub fn update(mouse: Vector, game: &mut Game) {
+    /// Move and aim with the controls, whose pointer is in the arena
+    pub fn update(controls: &Controls, game: &mut Game) {
+        let player = &mut game.player;
+        match game.rules.controls {
+            _ if player.dashing > 0 => {
+                player.dashing -= 1;
+                player.pos = (player.pos + player.dash_dir * DASH_SPEED).clamp(Vector::ZERO, game.arena);
+            }
+            ControlScheme::MouseFollow => {
+                // Move towards the cursor
+                let dir = controls.pointer - player.pos;
+                let dist = dir.len();
+                if dist > 4.0 {
+                    player.pos += dir * 0.2;
+                }
+            }
+            ControlScheme::KeysAndMouse | ControlScheme::KeysOnly => {
+                if controls.movement != Vector::ZERO {
+                    player.pos += controls.movement.normalize() * KEYS_SPEED;
+                    player.pos = player.pos.clamp(Vector::ZERO, game.arena);
+                }
+            }
+        }
+        if game.rules.controls.aims_with_mouse() {
+            player.aim = controls.pointer - player.pos;
+        } else if controls.aim != Vector::ZERO {
+            player.aim = controls.aim;
+        }
+
+        // Dash where it moves, or where it aims when it does not move with the keys
+        player.dash_cooldown = player.dash_cooldown.saturating_sub(1);
+        if controls.dash && player.dash > 0 && player.dash_cooldown == 0 {
+            let dir = if controls.movement != Vector::ZERO { controls.movement } else { player.aim };
+            if dir.len2() > 0.0 {
+                player.dashing = DASH_FRAMES;
+                player.dash_dir = dir.normalize();
+                player.dash_cooldown = DASH_RELOAD.saturating_sub(10 * (player.dash - 1)).max(MIN_DASH_RELOAD);
+                player.invincible = player.invincible.max(DASH_FRAMES as i32 + 1);
+                game.events.push(GameEvent::Dashed);
+            }
+        }
 
-        // Move towards the cursor
-        let dir = mouse - game.player.pos;
-        let dist = dir.len();
-        if dist > 4.0 {
-            game.player.pos += dir * 0.2;
+        for status in game.player.statuses.tick() {
+            game.events.push(GameEvent::StatusEnded(status));
         }
 
-        // Check collisions with enemies
+        // Check collisions with enemies, the boss and their bullets
         game.player.invincible -= 1;
         if game.player.invincible < 0 {
-            for e in &game.enemies {
-                if (e.pos - game.player.pos).len2() < (e.radius + game.player.radius).powi(2) {
-                    game.player.life -= 1;
-                    game.player.invincible = 30;  // 2/3 of a second
-                    game.shake += 12;
-
-                    if game.player.life > 0 {
-                        game.shots.extend(game.player.shot_hit());
-                    }
-                    break; // Only one life per frame
+            let (pos, radius) = (game.player.pos, game.player.radius);
+            let touches = |p: Vector, r: f32| (p - pos).len2() < (r + radius).powi(2);
+
+            // Enemies were split and bullets fired since the last build
+            game.grids.enemies.rebuild(game.enemies.iter().map(|e| (e.pos, e.radius)));
+            game.grids.shots.rebuild(game.shots.iter().map(|s| (s.pos, s.radius)));
+
+            let mut hit = game.grids.enemies
+                .query(pos, radius)
+                .into_iter()
+                .any(|i| touches(game.enemies[i].pos, game.enemies[i].radius))
+                || game.boss.as_ref().is_some_and(|b| touches(b.pos, b.radius));
+
+            if !hit {
+                let bullet = game.grids.shots
+                    .query(pos, radius)
+                    .into_iter()
+                    .find(|&i| {
+                        let s = &game.shots[i];
+                        s.owner == Faction::Enemy && s.alive && touches(s.pos, s.radius)
+                    });
+                if let Some(i) = bullet {
+                    game.shots[i].alive = false;
+                    hit = true;
+                }
+            }
+
+            // Only one life per frame
+            if hit {
+                game.player.life -= 1;
+                game.player.invincible = HURT_INVINCIBLE;
+                game.shake += 12;
+                game.events.push(GameEvent::PlayerHurt);
+                game.slowmo = SLOWMO_FRAMES * game.player.slowmo;
+
+                if game.player.life > 0 {
+                    game.shots.extend(game.player.shot_hit());
+                    game.events.push(GameEvent::ShotFired { laser: true });
                 }
             }
         }
 
-        // game.player.shoot_delay -= 1;
-        // if game.player.shoot_delay == 0 {
-        //     game.shots.extend(game.player.fire(mouse));
-        //     game.player.shoot_delay = SHOT_DELAY;
-        // }
+        // Auto-fire while the button is held
+        game.player.cooldown = game.player.cooldown.saturating_sub(1);
+        if controls.fire {
+            game.fire();
+        }
     }
 
     pub fn particles(&self, rng: & mut  XorShiftRng) -> Vec<Particle> {
@@ -75,7 +207,7 @@ impl Player {
         let angle = Uniform::new(0.0, 360.0);
         let speed = Normal::new(10.0, 3.0).unwrap();
 
-        (0..7).map(|_| {
+        let mut particles: Vec<Particle> = (0..7).map(|_| {
             let a = angle.sample(rng);
             
             Particle {
@@ -89,21 +221,91 @@ impl Player {
             alpha_scale: 20.0,
             color: hsv2rgb(a, 1.0, 1.0),
             ..Particle::default()
-        }}).collect()
+        }}).collect();
+
+        particles.extend((0..self.dashing * 3).map(|_| Particle {
+            // A streak behind the dash
+            pos: self.pos,
+            speed: speed.sample(rng) as f32 * 2.0,
+            angle: 180.0 + self.dash_dir.angle() + angle.sample(rng) / 8.0 - 22.5,
+            accel: -2.0,
+            shape: Shape::Shard(0.5, 3.0, true),
+            color: Power::Dash.color(),
+            ..Particle::default()
+        }));
+        particles
+    }
+
+    /// Where the shield shards are at this frame
+    pub fn shards(&self, frame: u32) -> impl Iterator<Item = Vector> + '_ {
+        let n = self.shields;
+        (0..n).map(move |i| {
+            let a = frame as f32 * SHIELD_SPEED + 360.0 * i as f32 / n as f32;
+            self.pos + Vector::from_angle(a) * SHIELD_DISTANCE
+        })
+    }
+
+    pub fn evolved(&self, evolution: Evolution) -> bool {
+        self.evolutions.contains(&evolution)
     }
 
-    pub fn fire(&self, aim: Vector) -> Vec<Shot> {
-        let angle =(aim - self.pos).angle();
+    /// Ticks between two volleys, with rapid fire
+    pub fn volley_delay(&self) -> u32 {
+        if self.statuses.has(Status::RapidFire) {
+            (self.fire_delay / 2).max(1)
+        } else {
+            self.fire_delay
+        }
+    }
+
+    /// Of each shot, with berserk
+    pub fn shot_damage(&self) -> i32 {
+        if self.statuses.has(Status::Berserk) { self.damage * 2 } else { self.damage }
+    }
+
+    /// The damage it deals in a second when each shot hits one enemy
+    pub fn dps(&self) -> f32 {
+        (self.shots * self.shot_damage()) as f32 * 30.0 / self.volley_delay() as f32
+    }
+
+    /// A volley towards `aim`
+    pub fn fire(&self) -> Vec<Shot> {
+        let angle = self.aim.angle();
+        let berserk = self.statuses.has(Status::Berserk);
+        let damage = self.shot_damage();
 
         (0..self.shots).map(|i| {
-            let a = angle - 15.0 * (i as f32 - self.shots as f32 / 2.0);
+            let a = if self.evolved(Evolution::Ring) {
+                angle + 360.0 * i as f32 / self.shots as f32
+            } else {
+                angle - 15.0 * (i as f32 - self.shots as f32 / 2.0)
+            };
 
-            Shot::new(
-                self.pos, 
-                Vector::from_angle(a) * SHOT_SPEED,
-                self.pierce,
-                self.damage,
-            )
+            let shot = if self.evolved(Evolution::Lasers) {
+                Shot::laser(self.pos, a, damage)
+            } else {
+                Shot::new(
+                    self.pos,
+                    Vector::from_angle(a) * SHOT_SPEED,
+                    self.pierce,
+                    damage,
+                )
+            };
+            Shot {
+                radius: shot.radius * (1.0 + SHOT_GROWTH * self.shot_size as f32),
+                homing: HOMING_TURN * self.homing as f32,
+                bounces: self.bounces,
+                burns: berserk,
+                // Tinted by what makes it special
+                color: if self.homing > 0 {
+                    Power::Homing.color()
+                } else if self.bounces > 0 {
+                    Power::Ricochet.color()
+                } else {
+                    shot.color
+                },
+                ..shot
+            }
         }).collect()
     }
 
@@ -126,8 +328,26 @@ impl Player {
             Power::PierceUp => self.pierce += 1,
             Power::ShotUp => self.shots += 1,
             Power::DamageUp => self.damage += 1,
+            Power::FireRate => self.fire_delay = (self.fire_delay - 1).max(MIN_FIRE_DELAY),
+            Power::Homing => self.homing += 1,
+            Power::Ricochet => self.bounces += 1,
+            Power::Shield => self.shields += 1,
+            Power::Dash => self.dash += 1,
+            Power::SlowMo => self.slowmo += 1,
+            Power::ChainLightning => self.chain += 1,
+            Power::Magnet => self.magnet += 1,
+            Power::BigShots => self.shot_size += 1,
+            // They go through `Game::take_power`
+            Power::RapidFire | Power::Berserk | Power::Freeze => {}
         };
+        self.powers.push(up);
 
         vec![]
     }
 }
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,163 @@
+//! What the powers do besides changing the numbers of the player.
+//!
+//! They run each tick before the enemies move, so that the damage they
+//! deal is handled with that of the shots: enemies still die, split and
+//! score in `Enemy::update`. Lightning from the hits of a tick jumps on
+//! the next one.
+
+use quicksilver::geom::Vector;
+use std::mem::take;
+
+use super::{Game, Faction, Power, Particle, Shape, GameEvent, Evolution, SHARD_RADIUS, SHOT_SPEED};
+use crate::enemy::angle_diff;
+
+/// How far homing shots look for a target
+const HOMING_RANGE: f32 = 400.0;
+/// How far lightning jumps
+const CHAIN_RANGE: f32 = 250.0;
+/// How far the magnet reaches, for each magnet powerup
+pub const MAGNET_RANGE: f32 = 200.0;
+/// Per tick
+const MAGNET_PULL: f32 = 8.0;
+/// How long time slows down when the player is hurt, for each slow-mo powerup
+pub const SLOWMO_FRAMES: u32 = 60;
+/// How fast enemies, the boss, their bullets and their timers go in slow motion
+pub const SLOWMO_SCALE: f32 = 0.4;
+
+/// Lightning that will jump from `from` to the closest enemies
+#[derive(Copy, Clone, Debug)]
+pub struct Zap {
+    pub from: Vector,
+    pub jumps: u32,
+}
+
+pub fn update(game: &mut Game) {
+    game.slowmo = game.slowmo.saturating_sub(1);
+
+    // Enemies were split and spawned since the last build
+    game.grids.enemies.rebuild(game.enemies.iter().map(|e| (e.pos, e.radius)));
+
+    steer_shots(game);
+    shield(game);
+    lightning(game);
+    magnet(game);
+}
+
+/// Turn the homing shots towards the closest enemy, or the boss
+fn steer_shots(game: &mut Game) {
+    let (enemies, grid) = (&game.enemies, &game.grids.enemies);
+    let boss = game.boss.as_ref().map(|b| b.pos);
+    for s in &mut game.shots {
+        if s.owner != Faction::Player || s.homing == 0.0 {
+            continue;
+        }
+        let target = grid
+            .query(s.pos, HOMING_RANGE)
+            .into_iter()
+            .map(|i| enemies[i].pos)
+            .chain(boss)
+            .filter(|p| (*p - s.pos).len2() < HOMING_RANGE.powi(2))
+            .min_by(|a, b| (*a - s.pos).len2().total_cmp(&(*b - s.pos).len2()));
+
+        if let Some(target) = target {
+            let diff = angle_diff((target - s.pos).angle(), s.vel.angle());
+            let angle = s.vel.angle() + diff.clamp(-s.homing, s.homing);
+            s.vel = Vector::from_angle(angle) * s.vel.len();
+        }
+    }
+}
+
+/// The shards stop the bullets, or send them back once evolved
+/// in a reflector, and hurt the enemies they touch
+fn shield(game: &mut Game) {
+    let shards: Vec<Vector> = game.player.shards(game.frame).collect();
+    if shards.is_empty() {
+        return;
+    }
+
+    let reflect = game.player.evolved(Evolution::Reflector);
+    for s in &mut game.shots {
+        if s.owner == Faction::Enemy && shards.iter().any(|&p| (s.pos - p).len2() < (s.radius + SHARD_RADIUS).powi(2)) {
+            if reflect {
+                s.owner = Faction::Player;
+                s.vel = (s.pos - game.player.pos).normalize() * SHOT_SPEED / 2.0;
+                s.damage = game.player.damage;
+                s.color = Power::Shield.color();
+            } else {
+                s.alive = false;
+            }
+        }
+    }
+
+    let mut enemies = take(&mut game.enemies);
+    for &p in &shards {
+        for i in game.grids.enemies.query(p, SHARD_RADIUS) {
+            let e = &mut enemies[i];
+            if e.alive() && !e.invincible() && (e.pos - p).len2() < (e.radius + SHARD_RADIUS).powi(2) {
+                let angle = (e.pos - game.player.pos).angle();
+                e.hurt(game, 1, angle);
+            }
+        }
+    }
+    game.enemies = enemies;
+}
+
+/// Each zap jumps from enemy to enemy, hurting each one once
+fn lightning(game: &mut Game) {
+    let zaps = take(&mut game.zaps);
+    if zaps.is_empty() {
+        return;
+    }
+
+    let mut enemies = take(&mut game.enemies);
+    for zap in zaps {
+        let mut from = zap.from;
+        for _ in 0..zap.jumps {
+            let next = game.grids.enemies
+                .query(from, CHAIN_RANGE)
+                .into_iter()
+                .filter(|&i| enemies[i].alive() && !enemies[i].invincible())
+                .filter(|&i| (enemies[i].pos - from).len2() < CHAIN_RANGE.powi(2))
+                .min_by(|&a, &b| (enemies[a].pos - from).len2().total_cmp(&(enemies[b].pos - from).len2()));
+
+            let i = match next {
+                Some(i) => i,
+                None => break,
+            };
+            let to = enemies[i].pos;
+            arc(game, from, to);
+            enemies[i].hurt(game, 1, (to - from).angle());
+            game.events.push(GameEvent::Zapped);
+            from = to;
+        }
+    }
+    game.enemies = enemies;
+}
+
+/// Sparks along the way of the lightning
+fn arc(game: &mut Game, from: Vector, to: Vector) {
+    let steps = ((to - from).len() / 20.0).ceil().max(1.0) as u32;
+    for i in 0..=steps {
+        game.particles.push(Particle {
+            pos: from + (to - from) * (i as f32 / steps as f32),
+            speed: 6.0,
+            angle: (to - from).angle() + if i % 2 == 0 { 90.0 } else { -90.0 },
+            accel: -1.0,
+            shape: Shape::Circle(2.0),
+            color: Power::ChainLightning.color(),
+            alpha_scale: 6.0,
+            ..Particle::default()
+        });
+    }
+}
+
+/// Pull the flying powerups closer, not the offers which are a choice
+fn magnet(game: &mut Game) {
+    let range = MAGNET_RANGE * game.player.magnet as f32;
+    for p in &mut game.powerups {
+        let dir = game.player.pos - p.pos;
+        if !p.offer && dir.len2() < range.powi(2) && dir.len2() > 0.0 {
+            p.pos += dir.normalize() * MAGNET_PULL.min(dir.len());
+        }
+    }
+}
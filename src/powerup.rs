@@ -1,16 +1,71 @@
-use quicksilver::geom::{Vector, Circle};
-use quicksilver::graphics::{Color, Graphics};
+use quicksilver::geom::Vector;
+use quicksilver::graphics::Color;
 use rand_distr::{Uniform, Normal, Distribution};
-use super::{XorShiftRng, SIZE, Particle, Shape, Player, in_screen};
+use serde::{Serialize, Deserialize};
+use super::{XorShiftRng, Particle, Shape, Camera, Status};
 
-const POWER_CIRCLES: i32 = 7;
-
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(clippy::enum_variant_names)]
 pub enum Power {
     LifeUp,
     PierceUp,
     ShotUp,
     DamageUp,
+    /// Less time between two volleys
+    FireRate,
+    /// The shots turn towards the closest enemy
+    Homing,
+    /// The shots bounce on the edges of the arena
+    Ricochet,
+    /// A shard that turns around the player, stops bullets and hurts enemies
+    Shield,
+    /// A quick move during which nothing hurts
+    Dash,
+    /// Time slows down for a while when the player is hurt
+    SlowMo,
+    /// Hits jump to the enemies around
+    ChainLightning,
+    /// Brings the flying powerups closer
+    Magnet,
+    BigShots,
+    /// Timed, only found flying around
+    RapidFire,
+    Berserk,
+    /// Slows down the enemies on screen for a while
+    Freeze,
+}
+
+impl Power {
+    pub fn color(self) -> Color {
+        match self {
+            Power::LifeUp => Color::from_hex("#26A65B"),
+            Power::PierceUp => Color::from_hex("#BF55EC"),
+            Power::ShotUp => Color::from_hex("#F22613"),
+            Power::DamageUp => Color::ORANGE,
+            Power::FireRate => Color::from_hex("#F7CA18"),
+            Power::Homing => Color::from_hex("#19B5FE"),
+            Power::Ricochet => Color::from_hex("#D2527F"),
+            Power::Shield => Color::from_hex("#BDC3C7"),
+            Power::Dash => Color::from_hex("#4183D7"),
+            Power::SlowMo => Color::from_hex("#16A085"),
+            Power::ChainLightning => Color::from_hex("#7FFFD4"),
+            Power::Magnet => Color::from_hex("#6C7A89"),
+            Power::BigShots => Color::from_hex("#F1A9A0"),
+            Power::RapidFire => Color::from_hex("#FDE3A7"),
+            Power::Berserk => Color::from_hex("#96281B"),
+            Power::Freeze => Color::from_hex("#C5EFF7"),
+        }
+    }
+
+    /// The status it gives, for the timed ones
+    pub fn status(self) -> Option<Status> {
+        match self {
+            Power::RapidFire => Some(Status::RapidFire),
+            Power::Berserk => Some(Status::Berserk),
+            Power::Freeze => Some(Status::Freeze),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -21,15 +76,20 @@ pub struct PowerUp {
     pub hits: i32,
     pub radius: f32,
     pub t: f32,
+    /// Offered at the end of a wave, as opposed to flying around
+    pub offer: bool,
 }
 
 impl PowerUp {
-    pub fn new(power: Power, rng: &mut XorShiftRng) -> Self {
-        let unif = Uniform::new(-100.0, SIZE.x + 100.0);
-        let mut pos = Vector::ONE;
-        while in_screen(&pos) {
-            pos.x = unif.sample(rng);
-            pos.y = unif.sample(rng);
+    /// Flying in from just out of the view of the camera
+    pub fn new(power: Power, camera: &Camera, rng: &mut XorShiftRng) -> Self {
+        let view = camera.view();
+        let x = Uniform::new(view.pos.x - 100.0, view.pos.x + view.size.x + 100.0);
+        let y = Uniform::new(view.pos.y - 100.0, view.pos.y + view.size.y + 100.0);
+        let mut pos = view.pos;
+        while camera.sees(pos, 50.0) {
+            pos.x = x.sample(rng);
+            pos.y = y.sample(rng);
         }
 
         let angle = Uniform::new(0.0, 360.0);
@@ -37,33 +97,30 @@ impl PowerUp {
 
 
         PowerUp {
-            pos: pos,
+            pos,
             vel: Vector::from_angle(angle.sample(rng)) * length.sample(rng) as f32,
-            power: power,
+            power,
             hits: 5,
             radius: 20.0,
             t: 0.0,
+            offer: false,
         }
     }
 
     pub fn new_fixed(power: Power, pos: Vector) -> Self {
         PowerUp {
-            pos: pos,
-            power: power,
+            pos,
+            power,
             vel: Vector::ZERO,
             hits: 1,
             radius: 20.0,
             t: 0.0,
+            offer: true,
         }
     }
 
     pub fn color(&self) -> Color {
-        match self.power {
-            Power::LifeUp => Color::from_hex("#26A65B"),
-            Power::PierceUp => Color::from_hex("#BF55EC"),
-            Power::ShotUp => Color::from_hex("#F22613"),
-            Power::DamageUp => Color::ORANGE,
-        }
+        self.power.color()
     }
 
     pub fn particles(&self, rng: &mut XorShiftRng) -> Vec<Particle> {
@@ -87,40 +144,22 @@ impl PowerUp {
         }).collect()
     }
 
-    pub fn draw(&self, gfx: &mut Graphics, prop: f32) {
-        for i in 0..POWER_CIRCLES {
-            let a = 360.0 * (i as f32) / (POWER_CIRCLES as f32);
-            let angle = a + (self.t + prop) * 20.0;
-            gfx.fill_circle(
-                &Circle::new(
-                    self.pos + Vector::from_angle(angle) * self.radius,
-                    5.0,
-                ), 
-                Color::WHITE.with_alpha(0.7),
-            );
-        }
-    }
-
-    pub fn update(&mut self, player: &mut Player) {
+    /// Move and bounce on the edges of an arena of this size,
+    /// the player picks it in `Game::update`
+    pub fn update(&mut self, arena: Vector) {
         self.t += 1.0;
         self.pos += self.vel;
 
         if (self.pos.x < 0.0 && self.vel.x < 0.0)
-            || (self.pos.x > SIZE.x && self.vel.x > 0.0) {
+            || (self.pos.x > arena.x && self.vel.x > 0.0) {
             self.vel.x *= -1.0;
             self.hits -= 1;
         }
 
         if (self.pos.y < 0.0 && self.vel.y < 0.0)
-            || (self.pos.y > SIZE.y && self.vel.y > 0.0) {
+            || (self.pos.y > arena.y && self.vel.y > 0.0) {
             self.vel.y *= -1.0;
             self.hits -= 1;
         }
-
-        if self.pos.distance(player.pos) < self.radius + player.radius {
-            self.hits = 0;
-            player.powerup(self.power);
-        }
-
     }
 }
\ No newline at end of file
@@ -0,0 +1,598 @@
+use quicksilver::{
+    geom::{Vector, Transform, Circle, Rectangle},
+    graphics::{Color, Vertex, Mesh, Element},
+    Graphics,
+};
+use rand::prelude::*;
+use rand_distr::*;
+use rand_xorshift::XorShiftRng;
+
+use chaos::{Game, WaveState, SIZE};
+use chaos::particles::{Particle, Shape};
+use chaos::enemy::{Enemy, EnemyKind, ARMOR_OPENING};
+use chaos::boss::{Boss, BossPhase};
+use chaos::powerup::{Power, PowerUp};
+use chaos::status::Status;
+use chaos::waves::WaveScript;
+use chaos::evolution::Evolution;
+use chaos::player::{SHARD_RADIUS, HURT_INVINCIBLE};
+use chaos::powers::MAGNET_RANGE;
+use chaos::background::Background;
+use chaos::overlay::Overlay;
+use chaos::highscores::{HighScores, HIGHSCORES_SIZE};
+use chaos::menu::{Screens, Screen};
+use chaos::i18n::{Strings, Message, Msg};
+use chaos::controls::{Bindings, Command, key_name};
+
+use crate::layout::{Fonts, FontSize, Style, Align};
+
+const POWER_CIRCLES: i32 = 7;
+/// Rows of the high score table on the game over screen
+const HIGHSCORES_SHOWN: usize = 5;
+/// Between the text and the edges of the screen
+const MARGIN: f32 = 10.0;
+/// Between the dots on the floor of a big arena
+const ARENA_DOTS: f32 = 100.0;
+/// Between the off screen indicators and the edges of the screen
+const INDICATOR_MARGIN: f32 = 20.0;
+const MINIMAP_WIDTH: f32 = 200.0;
+/// Radius of the icons of the build panel
+const BUILD_ICON: f32 = 10.0;
+
+/// Anything that can be drawn straight from its simulation state.
+pub trait Draw {
+    /// `prop` is the proportion of time between the last update
+    /// and the next, in the range 0..1
+    fn draw(&self, gfx: &mut Graphics, prop: f32);
+}
+
+/// Draws a `Game` without ever modifying it.
+///
+/// The renderer has its own rng so that cosmetic randomness,
+/// like the screen shakes, does not change the course of the run.
+pub struct Renderer {
+    fonts: Fonts,
+    /// The language of the text
+    pub strings: Strings,
+    rng: XorShiftRng,
+    bg_color: Color,
+    /// The detailed stats of the player, toggled during a run
+    pub show_stats: bool,
+}
+
+impl Renderer {
+    pub fn new(fonts: Fonts, strings: Strings) -> Self {
+        Renderer {
+            fonts,
+            strings,
+            rng: XorShiftRng::from_seed([7; 16]),
+            bg_color: Color::from_hex("#020812"),
+            show_stats: false,
+        }
+    }
+
+    /// Draw the entire game on the gfx. `prop` is the
+    /// proportion of time between the last update and the next
+    /// prop is in the range 0..1
+    pub fn draw(&mut self, gfx: &mut Graphics, game: &Game, scores: &HighScores, screens: &Screens, prop: f32, _render_skip: usize) {
+        if game.paused || game.player.life == 0 {
+            // Otherwise things jitter when paused.
+            // prop = 0.0;
+        }
+        // What is outside of the virtual resolution is left black
+        gfx.clear(Color::BLACK);
+        gfx.fill_rect(&Rectangle::new_sized(SIZE), self.bg_color);
+        game.bg.draw(gfx, prop);
+
+        if screens.top().shows_game() {
+            self.draw_game(gfx, game, scores, &screens.bindings, prop);
+        }
+        self.draw_menu(gfx, screens, scores, &game.rules.waves);
+    }
+
+    fn draw_game(&mut self, gfx: &mut Graphics, game: &Game, scores: &HighScores, bindings: &Bindings, prop: f32) {
+
+        // The camera, shaken
+
+        let mut camera = game.camera.lerp(prop);
+        if game.shake > 0 {
+            let angle = Uniform::new(0.0, 360.0);
+            let unif = Uniform::new(5.0, 15.0);
+            camera += Vector::from_angle(angle.sample(&mut self.rng)) * unif.sample(&mut self.rng);
+        }
+        gfx.set_transform(Transform::translate(-camera));
+
+        if game.arena != SIZE {
+            draw_arena(gfx, game.arena, camera);
+        }
+
+        // Particles and poweups
+
+        for e in &game.enemies {
+            e.draw(gfx, prop);
+        }
+
+        for p in &game.particles {
+            p.draw(gfx, prop);
+        }
+
+        for p in &game.powerups {
+            p.draw(gfx, prop);
+        }
+
+        let player = &game.player;
+        if player.magnet > 0 {
+            gfx.stroke_circle(&Circle::new(player.pos, MAGNET_RANGE * player.magnet as f32), Power::Magnet.color().with_alpha(0.15));
+        }
+        for pos in player.shards(game.frame) {
+            gfx.fill_circle(&Circle::new(pos, SHARD_RADIUS), Power::Shield.color());
+        }
+
+        if let Some(b) = &game.boss {
+            b.draw(gfx, prop);
+        }
+
+        // On the screen
+
+        gfx.set_transform(Transform::IDENTITY);
+        if game.slowmo > 0 {
+            gfx.fill_rect(&Rectangle::new_sized(SIZE), Power::SlowMo.color().with_alpha(0.12));
+        }
+        if game.arena != SIZE {
+            draw_indicators(gfx, game, camera);
+            draw_minimap(gfx, game);
+        }
+
+        let score = game.score.to_string();
+        self.fonts.draw_spans(
+            gfx,
+            &[(self.strings.get(Msg::Score), Color::WHITE), (&score, Color::YELLOW)],
+            Vector::new(MARGIN, 50.0),
+            Style::new(FontSize::Big, Color::WHITE),
+        );
+
+        // The timed powers, with the seconds left
+        for (i, (status, frames)) in game.player.statuses.active().enumerate() {
+            let (name, color) = status_look(status);
+            let text = self.strings.text(&Message::new(Msg::StatusLeft).arg(self.strings.get(name)).arg(frames.div_ceil(30)));
+            self.fonts.draw(
+                gfx, &text, Vector::new(MARGIN, 90.0 + 35.0 * i as f32),
+                Style::new(FontSize::Small, color));
+        }
+
+        let life = "<3".repeat(game.player.life);
+        self.fonts.draw(
+            gfx, &life, Vector::new(SIZE.x - MARGIN, 50.0),
+            Style::new(FontSize::Big, Color::RED).align(Align::Right));
+
+        self.draw_build(gfx, game);
+        if self.show_stats {
+            self.draw_stats(gfx, game);
+        }
+
+        if let Some(b) = &game.boss {
+            draw_health_bar(gfx, b);
+        }
+
+        // Fades out at the end
+        if let Some((evolution, frames)) = game.evolved {
+            let text = self.strings.text(&Message::new(Msg::Evolved).arg(self.strings.get(evolution_name(evolution))));
+            self.fonts.draw(
+                gfx, &text, Vector::new(SIZE.x / 2.0, 150.0),
+                Style::new(FontSize::Medium, Color::YELLOW.with_alpha((frames as f32 / 20.0).min(1.0))).align(Align::Center));
+        }
+
+        self.fonts.draw(
+            gfx, &self.strings.text(&Message::new(Msg::Seed).arg(game.seed)), Vector::new(MARGIN, SIZE.y - 15.0),
+            Style::new(FontSize::Small, Color::WHITE.with_alpha(0.5)));
+
+        if game.wave_state == WaveState::PowerUp && game.player.rerolls > 0 {
+            let key = key_name(bindings.key(Command::Reroll)).unwrap_or("?");
+            let text = self.strings.text(&Message::new(Msg::Rerolls).arg(key).arg(game.player.rerolls));
+            self.fonts.draw(
+                gfx, &text, Vector::new(SIZE.x / 2.0, SIZE.y - 15.0),
+                Style::new(FontSize::Small, Color::WHITE).align(Align::Center));
+        }
+
+        self.draw_overlay(gfx, &game.overlay);
+
+        if game.player.life == 0 {
+            self.draw_highscores(gfx, scores, SIZE.y / 2.0 + 130.0, HIGHSCORES_SHOWN);
+        }
+    }
+
+    /// The title and the items of the screen on top
+    fn draw_menu(&mut self, gfx: &mut Graphics, screens: &Screens, scores: &HighScores, waves: &WaveScript) {
+        gfx.set_transform(Transform::IDENTITY);
+        let screen = screens.top();
+
+        // The overlay of the game already says it on the others
+        let title = screen.title().map_or("", |t| self.strings.get(t)).to_string();
+        match screen {
+            Screen::Title => {
+                self.fonts.draw(
+                    gfx, &title, Vector::new(SIZE.x / 2.0, SIZE.y / 2.0),
+                    Style::new(FontSize::Big, Color::ORANGE).align(Align::Center));
+            }
+            Screen::Options | Screen::HighScores | Screen::HowToPlay | Screen::Keys => {
+                self.fonts.draw(
+                    gfx, &title, Vector::new(SIZE.x / 2.0, 120.0),
+                    Style::new(FontSize::Medium, Color::ORANGE).align(Align::Center));
+            }
+            _ => {}
+        }
+
+        match screen {
+            Screen::HighScores => self.draw_highscores(gfx, scores, 220.0, HIGHSCORES_SIZE),
+            Screen::HowToPlay => {
+                let style = Style::new(FontSize::Small, Color::WHITE).align(Align::Center).wrap(SIZE.x - 200.0);
+                let mut top = 230.0;
+                let text = screens.how_to_play(&self.strings, waves);
+                // Without bosses, their line is empty
+                for line in text.lines().filter(|l| !l.is_empty()) {
+                    top += self.fonts.draw(gfx, line, Vector::new(SIZE.x / 2.0, top), style).y + 15.0;
+                }
+            }
+            _ => {}
+        }
+
+        for (i, &item) in screen.items().iter().enumerate() {
+            let rect = screen.item_rect(i);
+            let color = if i == screens.selected {
+                gfx.fill_rect(&rect, Color::WHITE.with_alpha(0.15));
+                Color::YELLOW
+            } else {
+                Color::WHITE
+            };
+            self.fonts.draw_in(gfx, &screens.label(item, &self.strings), &rect, Style::new(FontSize::Small, color));
+        }
+    }
+
+    /// The best runs, from `top` down
+    fn draw_highscores(&mut self, gfx: &mut Graphics, scores: &HighScores, top: f32, rows: usize) {
+        if scores.entries().is_empty() {
+            self.fonts.draw(
+                gfx, self.strings.get(Msg::NoRuns), Vector::new(SIZE.x / 2.0, top),
+                Style::new(FontSize::Small, Color::WHITE).align(Align::Center));
+        }
+        for (i, e) in scores.entries().iter().take(rows).enumerate() {
+            let color = if scores.last_rank == Some(i) { Color::YELLOW } else { Color::WHITE };
+            let line = self.strings.text(
+                &Message::new(Msg::ScoreRow)
+                    .arg(format!("{:>2}", i + 1))
+                    .arg(format!("{:>6}", e.score))
+                    .arg(format!("{:<2}", e.wave))
+                    .arg(e.shots)
+                    .arg(e.pierce)
+                    .arg(e.damage)
+                    .arg(e.lives)
+                    .arg(e.seed)
+                    .arg(e.day()));
+            self.fonts.draw(
+                gfx, &line, Vector::new(60.0, top + i as f32 * 30.0),
+                Style::new(FontSize::Small, color));
+        }
+    }
+
+    /// Each power taken with how many times, under the lives, then the evolutions
+    fn draw_build(&mut self, gfx: &mut Graphics, game: &Game) {
+        let mut counts: Vec<(Power, usize)> = vec![];
+        for &p in &game.player.powers {
+            match counts.iter_mut().find(|c| c.0 == p) {
+                Some(c) => c.1 += 1,
+                None => counts.push((p, 1)),
+            }
+        }
+
+        let x = SIZE.x - MARGIN - BUILD_ICON * 4.0;
+        let mut y = 90.0;
+        for (power, n) in counts {
+            let center = Vector::new(x, y);
+            gfx.fill_circle(&Circle::new(center, BUILD_ICON), power.color());
+            gfx.stroke_circle(&Circle::new(center, BUILD_ICON + 3.0), power.rarity().color().with_alpha(0.7));
+            self.fonts.draw(
+                gfx, &format!("x{}", n), center + Vector::new(BUILD_ICON * 2.0, BUILD_ICON),
+                Style::new(FontSize::Small, Color::WHITE));
+            y += BUILD_ICON * 3.0;
+        }
+
+        for &e in &game.player.evolutions {
+            self.fonts.draw(
+                gfx, self.strings.get(evolution_name(e)), Vector::new(SIZE.x - MARGIN, y + BUILD_ICON),
+                Style::new(FontSize::Small, Color::YELLOW).align(Align::Right));
+            y += BUILD_ICON * 3.0;
+        }
+    }
+
+    /// The numbers behind the build, on a dark panel
+    fn draw_stats(&mut self, gfx: &mut Graphics, game: &Game) {
+        let player = &game.player;
+        let lines = [
+            Message::new(Msg::StatDps).arg(format!("{:.1}", player.dps())),
+            Message::new(Msg::StatShots).arg(player.shots),
+            Message::new(Msg::StatVolleys).arg(format!("{:.1}", 30.0 / player.volley_delay() as f32)),
+            Message::new(Msg::StatDamage).arg(player.shot_damage()),
+            Message::new(Msg::StatPierce).arg(player.pierce),
+            Message::new(Msg::StatInvincible).arg(player.invincible.max(0)).arg(HURT_INVINCIBLE),
+        ];
+
+        let line = 35.0;
+        let panel = Rectangle::new(
+            Vector::new(MARGIN, 230.0),
+            Vector::new(520.0, line * (lines.len() as f32 + 1.0) + 30.0),
+        );
+        gfx.fill_rect(&panel, Color::BLACK.with_alpha(0.6));
+        gfx.stroke_rect(&panel, Color::WHITE.with_alpha(0.3));
+
+        let mut pos = panel.pos + Vector::new(15.0, 45.0);
+        self.fonts.draw(gfx, self.strings.get(Msg::Stats), pos, Style::new(FontSize::Small, Color::ORANGE));
+        for message in &lines {
+            pos.y += line;
+            self.fonts.draw(gfx, &self.strings.text(message), pos, Style::new(FontSize::Small, Color::WHITE));
+        }
+    }
+
+    fn draw_overlay(&mut self, gfx: &mut Graphics, overlay: &Overlay) {
+        if !overlay.visible { return; }
+
+        let rect = Rectangle::new(
+            Vector::new(0.0, SIZE.y / 2.0 - overlay.height / 2.0),
+            Vector::new(SIZE.x, overlay.height),
+        );
+        gfx.fill_rect(&rect, Color::WHITE.with_alpha(0.2));
+
+        let style = Style::new(FontSize::Big, overlay.color);
+        let text = self.strings.text(&overlay.text);
+        match &overlay.subtitle {
+            None => self.fonts.draw_in(gfx, &text, &rect, style),
+            Some(subtitle) => {
+                let subtitle = self.strings.text(subtitle);
+                self.fonts.draw(gfx, &text, SIZE / 2.0, style.align(Align::Center));
+                self.fonts.draw(
+                    gfx, &subtitle, SIZE / 2.0 + Vector::new(0.0, 50.0),
+                    Style::new(FontSize::Small, Color::WHITE).align(Align::Center));
+            }
+        }
+    }
+}
+
+/// The edges of a big arena and dots on the floor, so the scrolling shows
+fn draw_arena(gfx: &mut Graphics, arena: Vector, camera: Vector) {
+    let color = Color::WHITE.with_alpha(0.08);
+    let first = Vector::new((camera.x / ARENA_DOTS).floor(), (camera.y / ARENA_DOTS).floor()) * ARENA_DOTS;
+    let mut y = first.y;
+    while y < camera.y + SIZE.y {
+        let mut x = first.x;
+        while x < camera.x + SIZE.x {
+            gfx.fill_rect(&Rectangle::new(Vector::new(x, y), Vector::new(3.0, 3.0)), color);
+            x += ARENA_DOTS;
+        }
+        y += ARENA_DOTS;
+    }
+    gfx.stroke_rect(&Rectangle::new_sized(arena), Color::ORANGE.with_alpha(0.6));
+}
+
+/// Arrows on the edges of the screen towards what is off screen
+fn draw_indicators(gfx: &mut Graphics, game: &Game, camera: Vector) {
+    let enemies = game.enemies.iter().map(|e| (e.pos, e.color));
+    let powerups = game.powerups.iter().map(|p| (p.pos, p.color()));
+    let boss = game.boss.iter().map(|b| (b.pos, Color::RED));
+
+    let center = SIZE / 2.0;
+    for (pos, color) in enemies.chain(powerups).chain(boss) {
+        if game.camera.sees(pos, 0.0) {
+            continue;
+        }
+        let on_screen = pos - camera;
+        let tip = on_screen.clamp(Vector::ONE * INDICATOR_MARGIN, SIZE - Vector::ONE * INDICATOR_MARGIN);
+        let dir = (on_screen - center).normalize();
+        let side = Vector::new(-dir.y, dir.x) * 7.0;
+        let back = tip - dir * 14.0;
+        gfx.fill_polygon(&[tip, back + side, back - side], color.with_alpha(0.7));
+    }
+}
+
+/// The whole arena in a corner
+fn draw_minimap(gfx: &mut Graphics, game: &Game) {
+    let scale = MINIMAP_WIDTH / game.arena.x;
+    let size = game.arena * scale;
+    let pos = SIZE - size - Vector::ONE * MARGIN;
+    let dot = |gfx: &mut Graphics, p: Vector, r: f32, color: Color| {
+        gfx.fill_rect(&Rectangle::new(pos + p * scale - Vector::ONE * r, Vector::ONE * 2.0 * r), color);
+    };
+
+    gfx.fill_rect(&Rectangle::new(pos, size), Color::BLACK.with_alpha(0.5));
+    gfx.stroke_rect(&Rectangle::new(pos, size), Color::WHITE.with_alpha(0.5));
+    let view = game.camera.view();
+    gfx.stroke_rect(&Rectangle::new(pos + view.pos * scale, view.size * scale), Color::WHITE.with_alpha(0.3));
+
+    for e in &game.enemies {
+        dot(gfx, e.pos, 1.0, e.color);
+    }
+    for p in &game.powerups {
+        dot(gfx, p.pos, 2.0, p.color());
+    }
+    if let Some(b) = &game.boss {
+        dot(gfx, b.pos, 4.0, Color::RED);
+    }
+    dot(gfx, game.player.pos, 2.0, Color::WHITE);
+}
+
+/// The name and color of a status in the HUD
+fn status_look(status: Status) -> (Msg, Color) {
+    match status {
+        Status::RapidFire => (Msg::RapidFire, Power::RapidFire.color()),
+        Status::Berserk => (Msg::Berserk, Power::Berserk.color()),
+        Status::Freeze => (Msg::Freeze, Power::Freeze.color()),
+        Status::Burn => (Msg::Burn, Color::ORANGE),
+    }
+}
+
+fn evolution_name(evolution: Evolution) -> Msg {
+    match evolution {
+        Evolution::Lasers => Msg::Lasers,
+        Evolution::Ring => Msg::Ring,
+        Evolution::Storm => Msg::Storm,
+        Evolution::Reflector => Msg::Reflector,
+    }
+}
+
+/// The life of the boss, at the top of the screen
+fn draw_health_bar(gfx: &mut Graphics, boss: &Boss) {
+    let size = Vector::new(600.0, 16.0);
+    let pos = Vector::new((SIZE.x - size.x) / 2.0, 70.0);
+    let ratio = boss.life.max(0) as f32 / boss.max_life as f32;
+
+    gfx.fill_rect(&Rectangle::new(pos, size), Color::WHITE.with_alpha(0.2));
+    gfx.fill_rect(&Rectangle::new(pos, Vector::new(size.x * ratio, size.y)), Color::RED);
+}
+
+impl Draw for Boss {
+    fn draw(&self, gfx: &mut Graphics, _prop: f32) {
+        gfx.fill_circle(&Circle::new(self.pos, self.radius), self.color().with_alpha(0.25));
+
+        // Show where it is about to rush
+        if let BossPhase::Charge(t, dir) = self.phase {
+            if t >= 20 {
+                let aim = self.pos + dir * (self.radius + 60.0);
+                gfx.fill_circle(&Circle::new(aim, 12.0), Color::RED.with_alpha(0.6));
+            }
+        }
+    }
+}
+
+impl Draw for Enemy {
+    fn draw(&self, gfx: &mut Graphics, _prop: f32) {
+        gfx.fill_circle(
+            &Circle::new(self.pos, self.radius),
+            self.tint().with_alpha(0.1)
+        );
+
+        // `n` dots every `step` degrees from `start`, turned with the enemy
+        let mut dots = |n: u32, start: f32, step: f32, dist: f32, size: f32, color: Color| {
+            for i in 0..n {
+                let a = self.angle + start + step * i as f32;
+                gfx.fill_circle(&Circle::new(self.pos + Vector::from_angle(a) * dist, size), color);
+            }
+        };
+
+        match self.kind {
+            EnemyKind::Slime => {}
+            // An eye, that lights up right before it fires
+            EnemyKind::Sniper => {
+                let alpha = if self.timer < 15 { 0.9 } else { 0.4 };
+                gfx.fill_circle(&Circle::new(self.pos, self.radius / 3.0), Color::RED.with_alpha(alpha));
+            }
+            // Same, before it dashes
+            EnemyKind::Dasher => {
+                let alpha = if self.timer < 15 { 0.9 } else { 0.3 };
+                gfx.fill_circle(&Circle::new(self.pos, self.radius / 3.0), Color::WHITE.with_alpha(alpha));
+            }
+            // Plates on the front, the back is open
+            EnemyKind::Armored => {
+                let front = 180.0 - ARMOR_OPENING;
+                dots(9, -front, front / 4.0, self.radius, 7.0, self.color.with_alpha(0.6));
+            }
+            // The three it will split into
+            EnemyKind::Splitter => {
+                dots(3, 0.0, 120.0, self.radius / 2.0, self.radius / 4.0, self.color.with_alpha(0.3));
+            }
+            // A fuse
+            EnemyKind::Kamikaze => {
+                gfx.fill_circle(&Circle::new(self.pos, self.radius / 2.0), Color::ORANGE.with_alpha(0.5));
+            }
+            // Its mouths, where the bullets come from
+            EnemyKind::Spitter => {
+                let mouths = 5 + self.level;
+                dots(mouths, 0.0, 360.0 / mouths as f32, self.radius, 4.0, self.color.with_alpha(0.6));
+            }
+        }
+    }
+}
+
+impl Draw for PowerUp {
+    fn draw(&self, gfx: &mut Graphics, prop: f32) {
+        // The offers show how rare they are
+        let color = if self.offer { self.power.rarity().color() } else { Color::WHITE };
+        for i in 0..POWER_CIRCLES {
+            let a = 360.0 * (i as f32) / (POWER_CIRCLES as f32);
+            let angle = a + (self.t + prop) * 20.0;
+            gfx.fill_circle(
+                &Circle::new(
+                    self.pos + Vector::from_angle(angle) * self.radius,
+                    5.0,
+                ),
+                color.with_alpha(0.7),
+            );
+        }
+    }
+}
+
+impl Draw for Background {
+    fn draw(&self, gfx: &mut Graphics, _prop: f32) {
+        let pts : Vec<Vector> = self.points
+            .iter()
+            .map(|p| p.draw_pos())
+            .collect();
+
+        gfx.stroke_path(&pts, self.color);
+    }
+}
+
+impl Draw for Particle {
+    fn draw(&self, gfx: &mut Graphics, prop: f32) {
+        match self.shape {
+            Shape::Circle(size) => {
+                gfx.fill_circle(
+                    &Circle::new(
+                        self.pos + Vector::from_angle(self.angle) * (self.speed * prop),
+                        size * self.speed / 3.3),
+                    self.color.with_alpha(self.speed / self.alpha_scale),
+                );
+            }
+            Shape::Shard(size, ratio, use_color) => {
+                let vertices = {
+                    let vel = Vector::from_angle(self.angle) * self.speed * size;
+                    let cross = Vector::new(-vel.y, vel.x);
+
+                    let front = Vertex {
+                        pos: self.pos + vel,
+                        uv: None,
+                        color: if use_color { self.color } else { Color::GREEN },
+                    };
+                    let left = Vertex {
+                        pos: self.pos + cross,
+                        uv: None,
+                        color: if use_color { self.color } else { Color::RED },
+                    };
+                    let right = Vertex {
+                        pos: self.pos - cross,
+                        uv: None,
+                        color: if use_color { self.color } else { Color::ORANGE } ,
+                    };
+                    let back = Vertex {
+                        pos: self.pos - vel * ratio,
+                        uv: None,
+                        color: if use_color { self.color } else { Color::BLUE.with_alpha(0.0) },
+                    };
+
+                    vec![front, left, right, back]
+                };
+                // A triangle is simply a pointer to indices of the vertices
+                let elements = vec![
+                    Element::Triangle([0, 1, 2]),
+                    Element::Triangle([1, 2, 3]),
+                ];
+                // Bring the vertices and the triangle elements together to define a mesh
+                let mesh = Mesh {
+                    vertices,
+                    elements,
+                    image: None,
+                };
+                // Pass a reference to the mesh to the graphics object to draw
+                gfx.draw_mesh(&mesh);
+            }
+        }
+    }
+}
+
@@ -0,0 +1,332 @@
+use quicksilver::{
+    geom::Vector,
+    input::{Event, MouseButton},
+};
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+use super::{Game, Rules, Controls};
+use crate::controls::{Bindings, Command};
+
+/// Bumped every time the format or the simulation changes in a way
+/// that makes older replays play out differently.
+pub const REPLAY_VERSION: u32 = 16;
+
+/// The only things a player can do besides moving the mouse.
+///
+/// Only firing and rerolling have their own input, the others are chosen in the menus.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Fire,
+    /// Draw other powerups, when some are offered
+    Reroll,
+    TogglePause,
+    /// Start again with the same seed
+    Restart,
+    /// Start again with another seed
+    NewSeed,
+}
+
+impl Action {
+    pub fn from_event(event: &Event, bindings: &Bindings) -> Option<Self> {
+        match event {
+            Event::PointerInput(p) if p.is_down() && p.button() == MouseButton::Left => Some(Action::Fire),
+            Event::KeyboardInput(k) if k.is_down() => match bindings.command(k.key()) {
+                Some(Command::Fire) => Some(Action::Fire),
+                Some(Command::Reroll) => Some(Action::Reroll),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// An action, with the mouse position it had when the event arrived.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimedAction {
+    pub action: Action,
+    #[serde(with = "vector")]
+    pub mouse: Vector,
+}
+
+/// Everything that happened between two updates.
+///
+/// The actions are applied in order, then the game is updated with the controls.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TickInput {
+    #[serde(with = "vector")]
+    pub mouse: Vector,
+    /// Keys held, for the schemes that use them
+    #[serde(default, skip_serializing_if = "is_zero", with = "vector")]
+    pub movement: Vector,
+    #[serde(default, skip_serializing_if = "is_zero", with = "vector")]
+    pub aim: Vector,
+    /// Fire held down
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub fire: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dash: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<TimedAction>,
+}
+
+impl TickInput {
+    pub fn apply(&self, game: &mut Game) {
+        for a in &self.actions {
+            game.action(a.action, a.mouse);
+        }
+        game.update(Controls {
+            pointer: self.mouse,
+            movement: self.movement,
+            aim: self.aim,
+            fire: self.fire,
+            dash: self.dash,
+        });
+    }
+}
+
+fn is_zero(v: &Vector) -> bool {
+    *v == Vector::ZERO
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub rules: Rules,
+    /// The score at the end of the recording, to check claims against.
+    pub score: u32,
+    pub ticks: Vec<TickInput>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Json(serde_json::Error),
+    Version(u32),
+    /// The rules would make the game panic
+    Rules(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Json(e) => write!(f, "invalid replay file: {}", e),
+            ReplayError::Version(v) => write!(
+                f, "replay version {} can not be played by version {}", v, REPLAY_VERSION),
+            ReplayError::Rules(e) => write!(f, "invalid rules in the replay: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    pub fn from_json(data: &[u8]) -> Result<Self, ReplayError> {
+        let replay: Replay = serde_json::from_slice(data).map_err(ReplayError::Json)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::Version(replay.version));
+        }
+        replay.rules.check().map_err(ReplayError::Rules)?;
+        Ok(replay)
+    }
+
+    pub fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("a replay is always serializable")
+    }
+
+    /// Play the whole replay without any window and return the final state.
+    pub fn play(&self) -> Game {
+        let mut game = Game::with_rules(self.seed, self.rules.clone());
+        for tick in &self.ticks {
+            tick.apply(&mut game);
+        }
+        game
+    }
+
+    /// Whether playing the replay gives back the recorded score.
+    pub fn verify(&self) -> bool {
+        self.play().score == self.score
+    }
+}
+
+/// Collects the inputs of a live game, tick by tick.
+pub struct Recorder {
+    replay: Replay,
+    pending: Vec<TimedAction>,
+}
+
+impl Recorder {
+    pub fn new(seed: u64, rules: Rules) -> Self {
+        Recorder {
+            replay: Replay {
+                version: REPLAY_VERSION,
+                seed,
+                rules,
+                score: 0,
+                ticks: vec![],
+            },
+            pending: vec![],
+        }
+    }
+
+    /// Record an action, to be called right before `Game::action`.
+    pub fn action(&mut self, action: Action, mouse: Vector) {
+        self.pending.push(TimedAction { action, mouse });
+    }
+
+    /// Record an update, to be called right before `Game::update`.
+    pub fn tick(&mut self, controls: Controls) {
+        self.replay.ticks.push(TickInput {
+            mouse: controls.pointer,
+            movement: controls.movement,
+            aim: controls.aim,
+            fire: controls.fire,
+            dash: controls.dash,
+            actions: self.pending.drain(..).collect(),
+        });
+    }
+
+    /// The replay of everything so far, with the given final score.
+    pub fn replay(&self, score: u32) -> Replay {
+        Replay {
+            score,
+            ..self.replay.clone()
+        }
+    }
+}
+
+/// (De)serialize a Vector as a pair of floats.
+mod vector {
+    use quicksilver::geom::Vector;
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+    pub fn serialize<S: Serializer>(v: &Vector, s: S) -> Result<S::Ok, S::Error> {
+        (v.x, v.y).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vector, D::Error> {
+        let (x, y) = Deserialize::deserialize(d)?;
+        Ok(Vector::new(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BigArena, ControlScheme, Flocking, Power, WaveState, GameEvent};
+    use crate::waves::EndCondition;
+
+    /// A short first wave that offers a dash, under keyboard controls
+    fn rules() -> Rules {
+        let mut rules = Rules { controls: ControlScheme::KeysAndMouse, ..Rules::default() };
+        rules.waves.draft.rerolls = 2;
+        let first = &mut rules.waves.waves[0];
+        first.end = EndCondition::Frames(150);
+        first.powerups = vec![Power::Dash];
+        first.picks = 1;
+        rules
+    }
+
+    /// What a player would do on tick `t`
+    fn controls(game: &Game, t: u32) -> (Controls, Option<Action>) {
+        let toward = |target: Vector| {
+            let d = target - game.player.pos;
+            Vector::new(d.x.signum(), d.y.signum())
+        };
+        let offer = game.powerups.iter().find(|p| p.offer).map(|p| p.pos);
+        let enemy = game.enemies.first().map(|e| e.pos);
+
+        let mut controls = Controls::from(enemy.unwrap_or(game.player.pos) - game.camera.pos);
+        controls.fire = enemy.is_some();
+        controls.movement = match offer {
+            Some(pos) => toward(pos),
+            // Around the middle
+            None => toward(game.arena / 2.0 + Vector::from_angle(t as f32 * 3.0) * 200.0),
+        };
+        controls.dash = t.is_multiple_of(50);
+
+        let reroll = game.wave_state == WaveState::PowerUp && game.player.rerolls > 0;
+        (controls, reroll.then_some(Action::Reroll))
+    }
+
+    /// Play and record until the second wave is well started
+    fn record() -> (Game, Replay, Vec<GameEvent>) {
+        let rules = rules();
+        let mut game = Game::with_rules(7, rules.clone());
+        let mut recorder = Recorder::new(7, rules);
+        let mut events = vec![];
+        for t in 0..2000 {
+            let (controls, action) = controls(&game, t);
+            if let Some(action) = action {
+                recorder.action(action, controls.pointer);
+                game.action(action, controls.pointer);
+                events.extend(game.take_events());
+            }
+            recorder.tick(controls);
+            game.update(controls);
+            events.extend(game.take_events());
+            if game.wave == 2 && game.wave_state == WaveState::Ongoing && game.wave_frames > 200 {
+                break;
+            }
+        }
+        let replay = recorder.replay(game.score);
+        (game, replay, events)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (game, replay, events) = record();
+        // The recording has all kinds of inputs
+        assert!(game.player.life > 0 && game.wave == 2 && game.score > 0);
+        assert!(events.contains(&GameEvent::Rerolled));
+        assert!(events.contains(&GameEvent::Dashed));
+        assert!(replay.ticks.iter().any(|t| t.fire));
+        assert!(replay.ticks.iter().any(|t| t.movement != Vector::ZERO));
+
+        let loaded = Replay::from_json(&replay.to_json()).unwrap();
+        assert_eq!(loaded, replay);
+        assert!(loaded.verify());
+
+        let played = loaded.play();
+        assert_eq!(played.frame, game.frame);
+        assert_eq!(played.player.pos, game.player.pos);
+        assert_eq!(played.player.powers, game.player.powers);
+        assert_eq!(played.player.rerolls, game.player.rerolls);
+
+        let cheat = Replay { score: replay.score + 1, ..replay };
+        assert!(!cheat.verify());
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let mut replay = Recorder::new(0, Rules::default()).replay(0);
+        replay.version = REPLAY_VERSION - 1;
+        match Replay::from_json(&replay.to_json()) {
+            Err(ReplayError::Version(v)) => assert_eq!(v, REPLAY_VERSION - 1),
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(Replay::from_json(b"{}"), Err(ReplayError::Json(_))));
+    }
+
+    #[test]
+    fn broken_rules_are_rejected() {
+        let rejected = |edit: fn(&mut Rules)| {
+            let mut replay = Recorder::new(0, Rules::default()).replay(0);
+            edit(&mut replay.rules);
+            matches!(Replay::from_json(&replay.to_json()), Err(ReplayError::Rules(_)))
+        };
+        assert!(!rejected(|_| {}));
+        assert!(rejected(|r| r.waves.waves[0].spawn_every = 0));
+        assert!(rejected(|r| r.waves.waves.clear()));
+        assert!(rejected(|r| r.flocking = Some(Flocking { radius: 0.0, ..Flocking::default() })));
+        assert!(rejected(|r| r.flocking = Some(Flocking { radius: -1.0, ..Flocking::default() })));
+        assert!(rejected(|r| r.flocking = Some(Flocking { radius: 1e-6, ..Flocking::default() })));
+        assert!(!rejected(|r| r.flocking = Some(Flocking::default())));
+        assert!(rejected(|r| r.arena = Some(BigArena { width: 1e30, height: 2000.0 })));
+        assert!(!rejected(|r| r.arena = Some(BigArena::default())));
+    }
+}
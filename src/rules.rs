@@ -0,0 +1,28 @@
+use serde::{Serialize, Deserialize};
+
+use super::{WaveScript, Flocking, BigArena, ControlScheme};
+
+/// Everything that shapes a run besides its seed.
+///
+/// Rules are saved in replays, since the same inputs
+/// give another run under other rules.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rules {
+    pub waves: WaveScript,
+    /// Enemies move like boids when it is set
+    pub flocking: Option<Flocking>,
+    /// A world larger than the screen, that the camera scrolls through
+    pub arena: Option<BigArena>,
+    /// How the inputs move the player and aim
+    pub controls: ControlScheme,
+}
+
+impl Rules {
+    /// Whether a run can be played under these rules, for the rules
+    /// that come from a replay.
+    pub fn check(&self) -> Result<(), String> {
+        self.waves.check()?;
+        self.flocking.as_ref().map_or(Ok(()), Flocking::check)?;
+        self.arena.as_ref().map_or(Ok(()), BigArena::check)
+    }
+}
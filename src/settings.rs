@@ -0,0 +1,34 @@
+//! The choices of the player, kept between sessions.
+
+use quicksilver::{
+    log,
+    saving::{self, Location},
+};
+use serde::{Serialize, Deserialize};
+
+use crate::menu::MenuOptions;
+use crate::controls::Bindings;
+
+const APP_NAME: &str = "chaos";
+const PROFILE: &str = "settings";
+
+/// Saved to a file on desktop and to the localStorage on the web.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub options: MenuOptions,
+    pub bindings: Bindings,
+}
+
+impl Settings {
+    /// The saved settings, if there are some
+    pub fn load() -> Option<Self> {
+        saving::load(Location::Config, APP_NAME, PROFILE).ok()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = saving::save(Location::Config, APP_NAME, PROFILE, self) {
+            log::warn!("Could not save the settings: {}", e);
+        }
+    }
+}
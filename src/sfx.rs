@@ -0,0 +1,389 @@
+//! A small sound effect synthesizer, after sfxr.
+//!
+//! A sound is described by a handful of parameters: a waveform, a volume
+//! envelope, and how the frequency and the square duty cycle move over
+//! time. `SfxParams::synth` turns them into mono PCM samples, which
+//! `wav` can wrap in a file, so nothing here needs an audio device.
+
+use std::f32::consts::PI;
+
+use rand::prelude::*;
+use rand_xorshift::XorShiftRng;
+use serde::{Serialize, Deserialize};
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+/// Sounds never last longer than this many seconds
+const MAX_LENGTH: f32 = 5.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Waveform {
+    Square,
+    Sawtooth,
+    Sine,
+    Noise,
+}
+
+/// Everything that makes a sound. Times are in seconds and frequencies in Hz.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SfxParams {
+    pub wave: Waveform,
+
+    // Envelope
+    pub attack: f32,
+    pub sustain: f32,
+    /// Extra volume at the start of the sustain, fading during it
+    pub punch: f32,
+    pub decay: f32,
+
+    // Frequency
+    pub freq: f32,
+    /// The sound stops when a slide goes below this
+    pub min_freq: f32,
+    /// Octaves per second
+    pub slide: f32,
+    /// Change of the slide, in octaves per second per second
+    pub delta_slide: f32,
+    /// Relative depth of the vibrato, 0.5 goes half an octave up and down
+    pub vibrato_depth: f32,
+    pub vibrato_speed: f32,
+    /// The frequency is multiplied by this once, after `arp_time`
+    pub arp_mod: f32,
+    /// No arpeggio when 0
+    pub arp_time: f32,
+
+    // Square wave only
+    /// Fraction of the period that is high, between 0 and 1
+    pub duty: f32,
+    /// Change of the duty, per second
+    pub duty_sweep: f32,
+
+    /// The frequency, arpeggio and duty start over every `repeat` seconds, if not 0
+    pub repeat: f32,
+
+    // Filters
+    /// Low pass, from 0 (silence) to 1 (no filter)
+    pub lpf_cutoff: f32,
+    /// High pass, from 0 (no filter) to 1
+    pub hpf_cutoff: f32,
+
+    pub volume: f32,
+}
+
+impl Default for SfxParams {
+    fn default() -> Self {
+        SfxParams {
+            wave: Waveform::Square,
+            attack: 0.0,
+            sustain: 0.1,
+            punch: 0.0,
+            decay: 0.2,
+            freq: 440.0,
+            min_freq: 0.0,
+            slide: 0.0,
+            delta_slide: 0.0,
+            vibrato_depth: 0.0,
+            vibrato_speed: 0.0,
+            arp_mod: 1.0,
+            arp_time: 0.0,
+            duty: 0.5,
+            duty_sweep: 0.0,
+            repeat: 0.0,
+            lpf_cutoff: 1.0,
+            hpf_cutoff: 0.0,
+            volume: 0.5,
+        }
+    }
+}
+
+impl SfxParams {
+    /// Length of the envelope in seconds
+    pub fn duration(&self) -> f32 {
+        (self.attack + self.sustain + self.decay).min(MAX_LENGTH)
+    }
+
+    /// The volume of the envelope `t` seconds after the start
+    fn envelope(&self, t: f32) -> f32 {
+        if t < self.attack {
+            t / self.attack
+        } else if t < self.attack + self.sustain {
+            let s = (t - self.attack) / self.sustain;
+            1.0 + self.punch * (1.0 - s)
+        } else if t < self.duration() {
+            1.0 - (t - self.attack - self.sustain) / self.decay
+        } else {
+            0.0
+        }
+    }
+
+    /// The samples of the sound, between -1 and 1, at `SAMPLE_RATE`.
+    ///
+    /// Only the noise waveform uses `seed`, the same seed always
+    /// gives the same samples.
+    pub fn synth(&self, seed: u64) -> Vec<f32> {
+        let mut rng = XorShiftRng::seed_from_u64(seed);
+        let dt = 1.0 / SAMPLE_RATE as f32;
+        let len = (self.duration() * SAMPLE_RATE as f32) as usize;
+
+        let mut phase = 0.0;
+        let mut noise = rng.gen_range(-1.0, 1.0);
+        let mut low = 0.0;
+        let mut high = 0.0;
+
+        // Everything that starts over on a repeat
+        let mut freq = self.freq;
+        let mut slide = self.slide;
+        let mut duty = self.duty;
+        let mut arp_done = false;
+        let mut since_repeat = 0.0;
+
+        let mut samples = Vec::with_capacity(len);
+        for i in 0..len {
+            let t = i as f32 * dt;
+
+            if self.repeat > 0.0 && since_repeat >= self.repeat {
+                freq = self.freq;
+                slide = self.slide;
+                duty = self.duty;
+                arp_done = false;
+                since_repeat = 0.0;
+            }
+            if !arp_done && self.arp_time > 0.0 && since_repeat >= self.arp_time {
+                freq *= self.arp_mod;
+                arp_done = true;
+            }
+            since_repeat += dt;
+
+            slide += self.delta_slide * dt;
+            freq *= (slide * dt).exp2();
+            if freq < self.min_freq {
+                break;
+            }
+            duty = (duty + self.duty_sweep * dt).clamp(0.0, 1.0);
+
+            let vibrato = 1.0 + self.vibrato_depth * (2.0 * PI * self.vibrato_speed * t).sin();
+            phase += freq * vibrato * dt;
+            if phase >= 1.0 {
+                phase %= 1.0;
+                noise = rng.gen_range(-1.0, 1.0);
+            }
+
+            let x = match self.wave {
+                Waveform::Square => if phase < duty { 1.0 } else { -1.0 },
+                Waveform::Sawtooth => 1.0 - 2.0 * phase,
+                Waveform::Sine => (2.0 * PI * phase).sin(),
+                Waveform::Noise => noise,
+            };
+
+            low += self.lpf_cutoff * (x - low);
+            high += self.hpf_cutoff * (low - high);
+            let y = low - high;
+
+            samples.push((y * self.envelope(t) * self.volume).clamp(-1.0, 1.0));
+        }
+        samples
+    }
+}
+
+/// The sounds the game makes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Sfx {
+    Shot,
+    Laser,
+    EnemyHit,
+    Split,
+    EnemyDeath,
+    PowerUp,
+    PlayerHit,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 7] = [
+        Sfx::Shot,
+        Sfx::Laser,
+        Sfx::EnemyHit,
+        Sfx::Split,
+        Sfx::EnemyDeath,
+        Sfx::PowerUp,
+        Sfx::PlayerHit,
+    ];
+
+    pub fn params(self) -> SfxParams {
+        match self {
+            Sfx::Shot => SfxParams {
+                sustain: 0.03,
+                decay: 0.1,
+                freq: 900.0,
+                slide: -12.0,
+                duty: 0.3,
+                duty_sweep: 1.5,
+                hpf_cutoff: 0.01,
+                volume: 0.25,
+                ..SfxParams::default()
+            },
+            Sfx::Laser => SfxParams {
+                wave: Waveform::Sawtooth,
+                sustain: 0.1,
+                decay: 0.25,
+                freq: 1400.0,
+                min_freq: 100.0,
+                slide: -8.0,
+                vibrato_depth: 0.05,
+                vibrato_speed: 30.0,
+                lpf_cutoff: 0.6,
+                volume: 0.3,
+                ..SfxParams::default()
+            },
+            Sfx::EnemyHit => SfxParams {
+                wave: Waveform::Noise,
+                sustain: 0.02,
+                punch: 0.6,
+                decay: 0.1,
+                freq: 2500.0,
+                slide: -6.0,
+                volume: 0.3,
+                ..SfxParams::default()
+            },
+            Sfx::Split => SfxParams {
+                sustain: 0.04,
+                decay: 0.15,
+                freq: 300.0,
+                slide: 10.0,
+                arp_mod: 1.5,
+                arp_time: 0.05,
+                duty: 0.4,
+                lpf_cutoff: 0.5,
+                volume: 0.3,
+                ..SfxParams::default()
+            },
+            Sfx::EnemyDeath => SfxParams {
+                wave: Waveform::Noise,
+                sustain: 0.1,
+                punch: 0.5,
+                decay: 0.4,
+                freq: 1200.0,
+                slide: -3.0,
+                lpf_cutoff: 0.4,
+                volume: 0.4,
+                ..SfxParams::default()
+            },
+            Sfx::PowerUp => SfxParams {
+                sustain: 0.08,
+                decay: 0.3,
+                freq: 500.0,
+                slide: 3.0,
+                duty: 0.25,
+                repeat: 0.09,
+                volume: 0.3,
+                ..SfxParams::default()
+            },
+            Sfx::PlayerHit => SfxParams {
+                wave: Waveform::Sawtooth,
+                sustain: 0.05,
+                punch: 0.8,
+                decay: 0.35,
+                freq: 400.0,
+                min_freq: 40.0,
+                slide: -5.0,
+                vibrato_depth: 0.3,
+                vibrato_speed: 15.0,
+                lpf_cutoff: 0.3,
+                volume: 0.5,
+                ..SfxParams::default()
+            },
+        }
+    }
+}
+
+/// Samples as signed 16 bits integers
+pub fn pcm16(samples: &[f32]) -> Vec<i16> {
+    samples
+        .iter()
+        .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+        .collect()
+}
+
+/// A mono 16 bits WAV file of the samples
+pub fn wav(samples: &[f32]) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // Mono
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // Bytes per second
+    out.extend_from_slice(&2u16.to_le_bytes()); // Bytes per sample
+    out.extend_from_slice(&16u16.to_le_bytes()); // Bits per sample
+
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for s in pcm16(samples) {
+        out.extend_from_slice(&s.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sound() {
+        let params = Sfx::EnemyDeath.params();
+        assert_eq!(params.synth(7), params.synth(7));
+        assert_ne!(params.synth(7), params.synth(8));
+    }
+
+    #[test]
+    fn wav_header() {
+        let samples = Sfx::Shot.params().synth(0);
+        let file = wav(&samples);
+        let pcm = pcm16(&samples);
+        let u32_at = |i: usize| u32::from_le_bytes([file[i], file[i + 1], file[i + 2], file[i + 3]]);
+
+        assert_eq!(&file[0..4], b"RIFF");
+        assert_eq!(u32_at(4) as usize, file.len() - 8);
+        assert_eq!(&file[8..16], b"WAVEfmt ");
+        assert_eq!(u32_at(24), SAMPLE_RATE);
+        assert_eq!(&file[36..40], b"data");
+        assert_eq!(u32_at(40) as usize, pcm.len() * 2);
+        assert_eq!(file.len(), 44 + pcm.len() * 2);
+
+        let data: Vec<i16> = file[44..].chunks(2).map(|c| i16::from_le_bytes([c[0], c[1]])).collect();
+        assert_eq!(data, pcm);
+    }
+
+    #[test]
+    fn min_freq_ends_early() {
+        let params = SfxParams {
+            sustain: 1.0,
+            decay: 0.0,
+            freq: 1000.0,
+            slide: -4.0,
+            ..SfxParams::default()
+        };
+        let full = params.synth(0).len();
+        assert_eq!(full, SAMPLE_RATE as usize);
+
+        // Two octaves down after half a second
+        let short = SfxParams { min_freq: 250.0, ..params }.synth(0).len();
+        let half = SAMPLE_RATE as usize / 2;
+        assert!(short.abs_diff(half) < 10, "{} samples instead of about {}", short, half);
+    }
+
+    #[test]
+    fn every_sound() {
+        for &sound in &Sfx::ALL {
+            let samples = sound.params().synth(0);
+            assert!(!samples.is_empty(), "{:?} is empty", sound);
+            assert!(samples.iter().all(|s| (-1.0..=1.0).contains(s)), "{:?} clips", sound);
+            assert!(samples.iter().any(|&s| s != 0.0), "{:?} is silent", sound);
+        }
+    }
+}
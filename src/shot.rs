@@ -1,10 +1,17 @@
 use quicksilver::geom::Vector;
 use rand_distr::*;
-use super::{Particle, Color, XorShiftRng, Shape, in_screen, SHOT_SPEED};
+use super::{Particle, Color, XorShiftRng, Shape, in_arena, SHOT_SPEED};
 
+/// Who fired a shot, and so who it can hurt.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Faction {
+    Player,
+    Enemy,
+}
 
 #[derive(Copy, Clone, Debug)]
 pub struct Shot {
+    pub owner: Faction,
     pub pos: Vector,
     pub vel: Vector,
     pub radius: f32,
@@ -12,41 +19,91 @@ pub struct Shot {
     pub pierce: i32,
     pub damage: i32,
     pub laser: bool,
+    /// Of the trail, the color of whoever fired it
+    pub color: Color,
+    /// Degrees per tick it turns towards the closest enemy
+    pub homing: f32,
+    /// Times it can still bounce on the edges of the arena
+    pub bounces: u32,
+    /// Sets the enemies it hits on fire
+    pub burns: bool,
 }
 
 impl Shot {
     pub fn new(pos: Vector, vel: Vector, pierce: i32, damage: i32) -> Self {
         Shot {
+            owner: Faction::Player,
             pos,
             vel,
             radius: 15.0,
             alive: true,
-            pierce: pierce,
-            damage: damage,
+            pierce,
+            damage,
             laser: false,
+            color: Color::WHITE,
+            homing: 0.0,
+            bounces: 0,
+            burns: false,
         }
     }
 
     pub fn laser(pos: Vector, angle: f32, damage: i32) -> Self {
         Shot {
+            owner: Faction::Player,
             pos,
             vel: Vector::from_angle(angle) * SHOT_SPEED * 2.0,
             radius: 25.0,
             alive: true,
             pierce: 1000,
-            damage: damage,
+            damage,
             laser: true,
+            color: Color::WHITE,
+            homing: 0.0,
+            bounces: 0,
+            burns: false,
+        }
+    }
+
+    /// A bullet fired at the player, it disappears on the first hit.
+    pub fn hostile(pos: Vector, vel: Vector, color: Color) -> Self {
+        Shot {
+            owner: Faction::Enemy,
+            pos,
+            vel,
+            radius: 10.0,
+            alive: true,
+            pierce: 1,
+            damage: 1,
+            laser: false,
+            color,
+            homing: 0.0,
+            bounces: 0,
+            burns: false,
         }
     }
 
     pub fn particles(&self, rng: &mut XorShiftRng) -> Vec<Particle> {
+        if self.owner == Faction::Enemy {
+            return vec![Particle {
+                pos: self.pos,
+                speed: 6.0,
+                angle: 180.0 + self.vel.angle(),
+                accel: -1.0,
+                shape: Shape::Circle(self.radius / 3.0),
+                color: self.color,
+                alpha_scale: 6.0,
+                ..Particle::default()
+            }];
+        }
+
         // let angle = Normal::new(self.vel.angle() as f64 + 180.0, 10.0);
         let speed = Normal::new(15.0, 1.0).unwrap();
 
         let shape = if self.laser {
             Shape::Shard(-1.5, 5.0, true)
         } else {
-            Shape::Shard(-0.7 - 0.3 * self.pierce as f32, 3.0, false)
+            let size = (-0.7 - 0.3 * self.pierce as f32) * self.radius / 15.0;
+            Shape::Shard(size, 3.0, self.color != Color::WHITE)
         };
         
          (0..1).map(|_| Particle {
@@ -55,15 +112,31 @@ impl Shot {
             angle: 180.0 + self.vel.angle(),
             accel: -5.0,
             // angular_vel: angular_vel.sample(rng) as f32,
-            shape: shape,
-            color: Color::WHITE,
+            shape,
+            color: self.color,
             ..Particle::default()
         }).collect()
         
     }
-    pub fn update(&mut self) {
-        self.pos += self.vel;
+    /// Move `speed` times its velocity, and die out of an arena of this
+    /// size unless it can bounce back in
+    pub fn update(&mut self, arena: Vector, speed: f32) {
+        self.pos += self.vel * speed;
+
+        if self.bounces > 0 {
+            let out_x = (self.pos.x < 0.0 && self.vel.x < 0.0) || (self.pos.x > arena.x && self.vel.x > 0.0);
+            let out_y = (self.pos.y < 0.0 && self.vel.y < 0.0) || (self.pos.y > arena.y && self.vel.y > 0.0);
+            if out_x {
+                self.vel.x *= -1.0;
+            }
+            if out_y {
+                self.vel.y *= -1.0;
+            }
+            if out_x || out_y {
+                self.bounces -= 1;
+            }
+        }
 
-        self.alive = self.alive && in_screen(&self.pos) && self.pierce > 0;
+        self.alive = self.alive && in_arena(&self.pos, arena) && self.pierce > 0;
    }
 }
\ No newline at end of file
@@ -0,0 +1,199 @@
+//! Timed effects on the player and the enemies.
+//!
+//! Each status has a number of ticks left and of stacks. What a new one
+//! does to those depends on the status, see `Stacking`. What they do each
+//! tick is up to whoever has them, this only counts.
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    /// The player fires twice as fast
+    RapidFire,
+    /// The player deals double damage and sets enemies on fire
+    Berserk,
+    /// The enemy moves slower, more with each stack
+    Freeze,
+    /// The enemy loses life every now and then, more with each stack
+    Burn,
+}
+
+/// What applying a status again does
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Stacking {
+    /// The timer starts again
+    Refresh,
+    /// The time adds up, up to a limit
+    Extend(u32),
+    /// Adds a stack, up to a limit, and the timer starts again
+    Stack(u32),
+}
+
+impl Status {
+    pub const ALL: [Status; 4] = [Status::RapidFire, Status::Berserk, Status::Freeze, Status::Burn];
+
+    pub fn stacking(self) -> Stacking {
+        match self {
+            Status::RapidFire => Stacking::Extend(600),
+            Status::Berserk => Stacking::Refresh,
+            Status::Freeze | Status::Burn => Stacking::Stack(3),
+        }
+    }
+
+    /// How long it lasts, in ticks
+    pub fn duration(self) -> u32 {
+        match self {
+            Status::RapidFire => 300,
+            Status::Berserk => 240,
+            Status::Freeze => 150,
+            Status::Burn => 90,
+        }
+    }
+
+    fn idx(self) -> usize {
+        self as usize
+    }
+}
+
+/// The statuses someone has, and how long they last.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Statuses {
+    frames: [u32; 4],
+    stacks: [u32; 4],
+}
+
+impl Statuses {
+    pub fn apply(&mut self, status: Status) {
+        let (frames, stacks) = (&mut self.frames[status.idx()], &mut self.stacks[status.idx()]);
+        let duration = status.duration();
+        match status.stacking() {
+            Stacking::Refresh => *frames = duration,
+            Stacking::Extend(max) => *frames = (*frames + duration).min(max),
+            Stacking::Stack(max) => {
+                *frames = duration;
+                *stacks = (*stacks + 1).min(max);
+            }
+        }
+        *stacks = (*stacks).max(1);
+    }
+
+    pub fn has(&self, status: Status) -> bool {
+        self.frames[status.idx()] > 0
+    }
+
+    /// 0 when it does not have it
+    pub fn stacks(&self, status: Status) -> u32 {
+        self.stacks[status.idx()]
+    }
+
+    /// Ticks left
+    pub fn frames(&self, status: Status) -> u32 {
+        self.frames[status.idx()]
+    }
+
+    /// The statuses it has, with the ticks left
+    pub fn active(&self) -> impl Iterator<Item = (Status, u32)> + '_ {
+        Status::ALL.iter().map(move |&s| (s, self.frames(s))).filter(|s| s.1 > 0)
+    }
+
+    /// Count a tick, and give back the statuses that wore off
+    pub fn tick(&mut self) -> Vec<Status> {
+        let mut ended = vec![];
+        for &s in &Status::ALL {
+            let i = s.idx();
+            if self.frames[i] > 0 {
+                self.frames[i] -= 1;
+                if self.frames[i] == 0 {
+                    self.stacks[i] = 0;
+                    ended.push(s);
+                }
+            }
+        }
+        ended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::{Enemy, EnemyKind, BURN_PERIOD};
+    use crate::Game;
+    use quicksilver::geom::Vector;
+
+    #[test]
+    fn refresh_starts_again() {
+        let mut s = Statuses::default();
+        s.apply(Status::Berserk);
+        s.tick();
+        s.apply(Status::Berserk);
+        assert_eq!(s.frames(Status::Berserk), Status::Berserk.duration());
+        assert_eq!(s.stacks(Status::Berserk), 1);
+    }
+
+    #[test]
+    fn extend_adds_up_to_the_limit() {
+        let mut s = Statuses::default();
+        s.apply(Status::RapidFire);
+        s.tick();
+        s.apply(Status::RapidFire);
+        assert_eq!(s.frames(Status::RapidFire), 2 * Status::RapidFire.duration() - 1);
+        s.apply(Status::RapidFire);
+        assert_eq!(s.frames(Status::RapidFire), 600);
+        assert_eq!(s.stacks(Status::RapidFire), 1);
+    }
+
+    #[test]
+    fn stack_adds_up_to_the_limit() {
+        let mut s = Statuses::default();
+        for stacks in 1..=5 {
+            s.tick();
+            s.apply(Status::Freeze);
+            assert_eq!(s.stacks(Status::Freeze), stacks.min(3));
+            assert_eq!(s.frames(Status::Freeze), Status::Freeze.duration());
+        }
+    }
+
+    #[test]
+    fn expiry() {
+        let mut s = Statuses::default();
+        s.apply(Status::Burn);
+        s.apply(Status::Burn);
+        s.apply(Status::Berserk);
+        for _ in 1..Status::Burn.duration() {
+            assert!(s.tick().is_empty());
+        }
+        assert_eq!(s.tick(), vec![Status::Burn]);
+        assert!(!s.has(Status::Burn));
+        assert_eq!(s.stacks(Status::Burn), 0);
+        assert!(s.has(Status::Berserk));
+        assert_eq!(s.active().map(|a| a.0).collect::<Vec<_>>(), vec![Status::Berserk]);
+
+        // Stacking again starts from nothing
+        s.apply(Status::Burn);
+        assert_eq!(s.stacks(Status::Burn), 1);
+    }
+
+    #[test]
+    fn burn_hurts_enemies() {
+        let mut game = Game::new();
+        let mut enemy = Enemy::new(EnemyKind::Slime, game.player.pos + Vector::new(2000.0, 0.0), 10);
+        enemy.statuses.apply(Status::Burn);
+        enemy.statuses.apply(Status::Burn);
+
+        let life = enemy.life;
+        let mut lives = vec![];
+        for _ in 0..Status::Burn.duration() + BURN_PERIOD {
+            enemy.update(&mut game, Vector::ZERO);
+            lives.push(life - enemy.life);
+        }
+
+        // Two stacks, so two lives at each burn, until it wears off
+        let burns = Status::Burn.duration() / BURN_PERIOD;
+        assert_eq!(*lives.last().unwrap(), 2 * burns as i32);
+        for (t, pair) in (1u32..).zip(lives.windows(2)) {
+            let burns = t.is_multiple_of(BURN_PERIOD) && t < Status::Burn.duration();
+            assert_eq!(pair[1] - pair[0], if burns { 2 } else { 0 }, "tick {}", t);
+        }
+        assert!(!enemy.statuses.has(Status::Burn));
+    }
+}
@@ -0,0 +1,259 @@
+//! Wave scripts, loaded from `waves.json`.
+//!
+//! Each wave says how often enemies spawn, which ones, when it ends and
+//! which powerups are offered afterwards. After the last scripted wave,
+//! the last one is repeated and made harder by the `endless` rule.
+
+use rand::prelude::*;
+use rand_distr::*;
+use rand_xorshift::XorShiftRng;
+use serde::{Serialize, Deserialize};
+
+use super::{EnemyKind, Power, BossDef, Draft};
+
+/// The script shipped with the game, also used when `waves.json` is broken.
+pub const DEFAULT_WAVES: &str = include_str!("../static/waves.json");
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LevelDist {
+    /// All levels between the two, inclusive, are as likely
+    Uniform(u32, u32),
+    /// (level, weight) pairs
+    Weighted(Vec<(u32, u32)>),
+}
+
+impl LevelDist {
+    pub fn sample(&self, rng: &mut XorShiftRng) -> u32 {
+        match self {
+            LevelDist::Uniform(min, max) => Uniform::new_inclusive(*min, *max).sample(rng),
+            LevelDist::Weighted(levels) => levels
+                .choose_weighted(rng, |l| l.1)
+                .map_or(1, |l| l.0),
+        }
+    }
+
+    /// The same distribution with all the top levels `d` higher.
+    fn harder(&self, d: u32) -> Self {
+        match self {
+            LevelDist::Uniform(min, max) => LevelDist::Uniform(*min, max + d),
+            LevelDist::Weighted(levels) => LevelDist::Weighted(
+                levels.iter().map(|&(l, w)| (l + d, w)).collect()
+            ),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        match self {
+            LevelDist::Uniform(min, max) if min > max => Err(format!("the levels go from {} down to {}", min, max)),
+            LevelDist::Uniform(0, _) => Err("the levels start at 1".to_string()),
+            LevelDist::Uniform(..) => Ok(()),
+            LevelDist::Weighted(levels) if levels.iter().any(|l| l.0 == 0) => Err("the levels start at 1".to_string()),
+            LevelDist::Weighted(levels) if levels.iter().map(|l| l.1).sum::<u32>() == 0 => {
+                Err("the levels need a positive total weight".to_string())
+            }
+            LevelDist::Weighted(_) => Ok(()),
+        }
+    }
+}
+
+/// When a wave stops spawning enemies. It then ends once they are all dead.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EndCondition {
+    /// The total score of the run is above this
+    Score(u32),
+    /// This many enemies were killed during the wave
+    Kills(u32),
+    /// The wave lasted this many frames
+    Frames(u32),
+}
+
+impl EndCondition {
+    fn scaled(&self, factor: f32) -> Self {
+        let scale = |x: u32| (x as f32 * factor) as u32;
+        match *self {
+            EndCondition::Score(s) => EndCondition::Score(scale(s)),
+            EndCondition::Kills(k) => EndCondition::Kills(scale(k)),
+            EndCondition::Frames(f) => EndCondition::Frames(scale(f)),
+        }
+    }
+}
+
+fn default_kinds() -> Vec<(EnemyKind, u32)> {
+    vec![(EnemyKind::Slime, 1)]
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WaveDef {
+    /// An enemy spawns every `spawn_every` frames...
+    pub spawn_every: u32,
+    /// ...when the frame count modulo `spawn_every` is `spawn_offset`
+    pub spawn_offset: u32,
+    pub levels: LevelDist,
+    /// (kind, weight) pairs
+    #[serde(default = "default_kinds")]
+    pub kinds: Vec<(EnemyKind, u32)>,
+    pub end: EndCondition,
+    /// Offered when the wave is over
+    pub powerups: Vec<Power>,
+    /// How many of the offered powerups can be taken
+    pub picks: usize,
+}
+
+impl WaveDef {
+    pub fn kind(&self, rng: &mut XorShiftRng) -> EnemyKind {
+        self.kinds
+            .choose_weighted(rng, |k| k.1)
+            .map_or(EnemyKind::Slime, |k| k.0)
+    }
+
+    /// Whether the game can play it without dividing by zero or
+    /// sampling from nothing.
+    fn check(&self) -> Result<(), String> {
+        if self.spawn_every == 0 {
+            return Err("spawn_every must be positive".to_string());
+        }
+        if self.spawn_offset >= self.spawn_every {
+            return Err(format!("spawn_offset must be below spawn_every ({})", self.spawn_every));
+        }
+        self.levels.check()?;
+        if self.kinds.iter().map(|k| k.1).sum::<u32>() == 0 {
+            return Err("kinds need a positive total weight".to_string());
+        }
+        check_offers(&self.powerups, self.picks)
+    }
+}
+
+fn check_offers(powerups: &[Power], picks: usize) -> Result<(), String> {
+    if powerups.is_empty() {
+        return Err("there are no powerups to offer".to_string());
+    }
+    if picks == 0 {
+        return Err("picks must be at least 1".to_string());
+    }
+    // The draft only offers what lasts for the rest of the run
+    if let Some(p) = powerups.iter().find(|p| p.status().is_some()) {
+        return Err(format!("{:?} is timed, it can not be offered", p));
+    }
+    Ok(())
+}
+
+/// How the waves after the last scripted one get harder.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Endless {
+    /// Added to the top level each wave
+    pub level_step: u32,
+    /// The end condition is multiplied by this each wave
+    pub end_factor: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WaveScript {
+    pub waves: Vec<WaveDef>,
+    pub endless: Endless,
+    /// Without it, there are no boss waves
+    #[serde(default)]
+    pub boss: Option<BossDef>,
+    /// How the powerups are offered after the waves
+    #[serde(default)]
+    pub draft: Draft,
+}
+
+impl WaveScript {
+    pub fn from_json(data: &[u8]) -> serde_json::Result<Self> {
+        let script: WaveScript = serde_json::from_slice(data)?;
+        script.check().map_err(serde::de::Error::custom)?;
+        Ok(script)
+    }
+
+    /// Whether the game can play it without panicking, for the scripts
+    /// that come from a file or a replay.
+    pub fn check(&self) -> Result<(), String> {
+        if self.waves.is_empty() {
+            return Err("a wave script needs at least one wave".to_string());
+        }
+        for (i, wave) in self.waves.iter().enumerate() {
+            wave.check().map_err(|e| format!("wave {}: {}", i + 1, e))?;
+        }
+        if !(self.endless.end_factor.is_finite() && self.endless.end_factor > 0.0) {
+            return Err("endless: end_factor must be positive".to_string());
+        }
+        if let Some(boss) = &self.boss {
+            let check = if boss.minion_level == 0 {
+                Err("the levels start at 1".to_string())
+            } else {
+                check_offers(&boss.powerups, boss.picks)
+            };
+            check.map_err(|e| format!("boss: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Whether the wave `nb` is a boss fight instead of a normal wave.
+    pub fn is_boss_wave(&self, nb: u32) -> bool {
+        match &self.boss {
+            Some(b) => b.every > 0 && nb > 0 && nb.is_multiple_of(b.every),
+            None => false,
+        }
+    }
+
+    /// The definition of the wave `nb`, starting at 1.
+    pub fn wave(&self, nb: u32) -> WaveDef {
+        let idx = (nb.max(1) - 1) as usize;
+        match self.waves.get(idx) {
+            Some(w) => w.clone(),
+            None => {
+                let last = self.waves.last().unwrap();
+                let extra = (idx + 1 - self.waves.len()) as u32;
+                WaveDef {
+                    levels: last.levels.harder(extra * self.endless.level_step),
+                    end: last.end.scaled(self.endless.end_factor.powi(extra as i32)),
+                    ..last.clone()
+                }
+            }
+        }
+    }
+}
+
+impl Default for WaveScript {
+    fn default() -> Self {
+        WaveScript::from_json(DEFAULT_WAVES.as_bytes()).expect("The default waves are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The default script changed by `edit`
+    fn broken(edit: fn(&mut serde_json::Value)) -> serde_json::Result<WaveScript> {
+        let mut script: serde_json::Value = serde_json::from_str(DEFAULT_WAVES).unwrap();
+        edit(&mut script);
+        WaveScript::from_json(script.to_string().as_bytes())
+    }
+
+    #[test]
+    fn default_is_valid() {
+        assert!(WaveScript::from_json(DEFAULT_WAVES.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn rejects_broken_scripts() {
+        let edits: &[fn(&mut serde_json::Value)] = &[
+            |s| s["waves"][0]["spawn_every"] = 0.into(),
+            |s| s["waves"][0]["spawn_offset"] = s["waves"][0]["spawn_every"].clone(),
+            |s| s["waves"][0]["levels"] = serde_json::json!({"Uniform": [3, 1]}),
+            |s| s["waves"][0]["levels"] = serde_json::json!({"Uniform": [0, 1]}),
+            |s| s["waves"][0]["levels"] = serde_json::json!({"Weighted": [[2, 0]]}),
+            |s| s["waves"][0]["kinds"] = serde_json::json!([]),
+            |s| s["waves"][0]["powerups"] = serde_json::json!([]),
+            |s| s["waves"][0]["picks"] = 0.into(),
+            |s| s["endless"]["end_factor"] = 0.into(),
+            |s| s["boss"]["picks"] = 0.into(),
+            |s| s["waves"][0]["powerups"] = serde_json::json!(["DamageUp", "RapidFire"]),
+            |s| s["boss"]["powerups"] = serde_json::json!(["Freeze"]),
+        ];
+        for (i, &edit) in edits.iter().enumerate() {
+            assert!(broken(edit).is_err(), "broken script {} was accepted", i);
+        }
+    }
+}